@@ -3,7 +3,7 @@ use mcp::server::{MCPServer, StdioTransport};
 #[tokio::main]
 async fn main() {
     let transport = StdioTransport::new();
-    let mut server = MCPServer::new(
+    let server = MCPServer::new(
         transport,
         "basic",
         "0.1",
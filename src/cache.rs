@@ -0,0 +1,101 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::error::McpError;
+use crate::schema::original::ReadResourceResultContentsItem;
+use crate::server::Resource;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Cache of resource contents, keyed by URI. Clones share the same entries,
+/// so one cache can back several resources.
+#[derive(Clone, Debug)]
+pub struct ResourceCache {
+    entries: Arc<DashMap<String, (Instant, Vec<ReadResourceResultContentsItem>)>>,
+    /// How long an entry stays valid after it is read
+    ttl: Duration,
+}
+
+impl ResourceCache {
+    /// Constructor
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Returns the cached contents of a resource, if they have not expired
+    pub fn get(&self, uri: &str) -> Option<Vec<ReadResourceResultContentsItem>> {
+        let entry = self.entries.get(uri)?;
+        let (read_at, contents) = entry.value();
+        (read_at.elapsed() < self.ttl).then(|| contents.clone())
+    }
+
+    /// Caches the contents of a resource
+    pub fn insert(&self, uri: &str, contents: Vec<ReadResourceResultContentsItem>) {
+        self.entries.insert(uri.into(), (Instant::now(), contents));
+    }
+
+    /// Drops the cached contents of a resource, so the next read is fresh
+    pub fn invalidate(&self, uri: &str) {
+        self.entries.remove(uri);
+    }
+}
+
+/// A resource whose reads are served from a [`ResourceCache`] until they
+/// expire
+pub struct CachedResource<R: Resource> {
+    inner: R,
+    cache: ResourceCache,
+}
+
+impl<R: Resource> CachedResource<R> {
+    /// Constructor
+    pub fn new(inner: R, cache: ResourceCache) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<R: Resource> Resource for CachedResource<R> {
+    fn uri(&self) -> &str {
+        self.inner.uri()
+    }
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn description(&self) -> Option<&str> {
+        self.inner.description()
+    }
+    fn mime_type(&self) -> Option<&str> {
+        self.inner.mime_type()
+    }
+    async fn read(&self) -> Result<Vec<ReadResourceResultContentsItem>, McpError> {
+        if let Some(contents) = self.cache.get(self.inner.uri()) {
+            return Ok(contents);
+        }
+        let contents = self.inner.read().await?;
+        self.cache.insert(self.inner.uri(), contents.clone());
+        Ok(contents)
+    }
+    fn invalidate(&self) {
+        self.cache.invalidate(self.inner.uri());
+        self.inner.invalidate();
+    }
+}
@@ -0,0 +1,327 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::error::McpError;
+use crate::schema::original::{self, RequestId};
+use crate::schema::{
+    ClientCapabilities, ClientNotification, ClientRequest, ClientResult, Message, RootsCapability,
+    SamplingCapability, ServerNotification, ServerRequest, VERSION,
+};
+pub use crate::transport::{StdioTransport, Transport};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// A message received by the client. Results are kept as raw JSON until the
+/// caller says which result it expects, since they can't be told apart.
+type IncomingMessage = Message<ServerRequest, serde_json::Value, ServerNotification>;
+/// A message sent by the client
+type OutgoingMessage = Message<ClientRequest, ClientResult, ClientNotification>;
+
+/// Capabilities to advertise when initializing, on top of those set on the
+/// client
+#[derive(Debug, Clone, Default)]
+pub struct ExtraCapabilities {
+    /// Support for listing roots
+    pub roots: Option<RootsCapability>,
+    /// Support for sampling from an LLM
+    pub sampling: Option<SamplingCapability>,
+    /// Experimental, non-standard capabilities
+    pub experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+}
+
+/// an MCP client, capable of making requests of a server
+pub struct MCPClient<T: Transport> {
+    transport: Mutex<T>,
+    name: String,
+    version: String,
+    /// Experimental capabilities advertised to the server
+    experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    /// Experimental capabilities advertised by the server, once initialized
+    server_experimental: HashMap<String, serde_json::Value>,
+    /// ID of the next request
+    next_request_id: AtomicI64,
+    /// Cancelled when the connection is shut down or found to be dead
+    shutdown: CancellationToken,
+    /// Most pages fetched by the `list_all_*` methods
+    max_iterations: usize,
+}
+
+impl<T> MCPClient<T>
+where
+    T: Transport,
+{
+    /// Constructor
+    pub fn new(transport: T, name: &str, version: &str) -> Self {
+        Self {
+            transport: Mutex::new(transport),
+            name: name.into(),
+            version: version.into(),
+            experimental: HashMap::new(),
+            server_experimental: HashMap::new(),
+            next_request_id: AtomicI64::new(0),
+            shutdown: CancellationToken::new(),
+            max_iterations: 100,
+        }
+    }
+
+    /// Returns a token that is cancelled when the connection shuts down
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Sets the most pages the `list_all_*` methods fetch before giving up
+    /// on a server that never stops returning cursors. Defaults to 100.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Advertises an experimental, non-standard capability to the server
+    pub fn with_experimental_capability(
+        mut self,
+        key: &str,
+        value: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.experimental.insert(key.into(), value);
+        self
+    }
+
+    /// Returns an experimental capability advertised by the server, once
+    /// initialized
+    pub fn experimental_capability(&self, key: &str) -> Option<&serde_json::Value> {
+        self.server_experimental.get(key)
+    }
+
+    /// Performs the initialization handshake with the server, advertising
+    /// any extra capabilities given
+    pub async fn initialize(
+        &mut self,
+        extra: Option<ExtraCapabilities>,
+    ) -> Result<original::InitializeResult, McpError> {
+        let extra = extra.unwrap_or_default();
+        let mut experimental = self.experimental.clone();
+        experimental.extend(extra.experimental);
+        let params = original::InitializeRequestParams {
+            capabilities: ClientCapabilities {
+                experimental,
+                roots: extra.roots,
+                sampling: extra.sampling,
+            },
+            client_info: original::Implementation {
+                name: self.name.clone(),
+                version: self.version.clone(),
+            },
+            protocol_version: VERSION.into(),
+        };
+        let result: original::InitializeResult =
+            self.request(ClientRequest::Initialize(params)).await?;
+        self.server_experimental = result
+            .capabilities
+            .experimental
+            .iter()
+            .map(|(key, value)| (key.clone(), serde_json::Value::Object(value.clone())))
+            .collect();
+        self.notify(ClientNotification::Initialized(Default::default()))
+            .await?;
+        Ok(result)
+    }
+
+    /// Checks that the server is still responding
+    pub async fn ping(&self) -> Result<(), McpError> {
+        let _: original::ResultData = self
+            .request(ClientRequest::Ping(Default::default()))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists one page of the server's tools, starting at the cursor if given
+    pub async fn list_tools(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<original::ListToolsResult, McpError> {
+        self.request(ClientRequest::ListTools(original::ListToolsRequestParams {
+            cursor,
+        }))
+        .await
+    }
+
+    /// Lists one page of the server's resources, starting at the cursor if
+    /// given
+    pub async fn list_resources(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<original::ListResourcesResult, McpError> {
+        self.request(ClientRequest::ListResources(
+            original::ListResourcesRequestParams { cursor },
+        ))
+        .await
+    }
+
+    /// Lists one page of the server's prompts, starting at the cursor if given
+    pub async fn list_prompts(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<original::ListPromptsResult, McpError> {
+        self.request(ClientRequest::ListPrompts(
+            original::ListPromptsRequestParams { cursor },
+        ))
+        .await
+    }
+
+    /// Lists all of the server's tools, following cursors across pages
+    pub async fn list_all_tools(&mut self) -> Result<Vec<original::Tool>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor = None;
+        for _ in 0..self.max_iterations {
+            let page = self.list_tools(cursor).await?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(tools),
+            }
+        }
+        Err(McpError::TooManyPages(self.max_iterations))
+    }
+
+    /// Lists all of the server's resources, following cursors across pages
+    pub async fn list_all_resources(&mut self) -> Result<Vec<original::Resource>, McpError> {
+        let mut resources = Vec::new();
+        let mut cursor = None;
+        for _ in 0..self.max_iterations {
+            let page = self.list_resources(cursor).await?;
+            resources.extend(page.resources);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(resources),
+            }
+        }
+        Err(McpError::TooManyPages(self.max_iterations))
+    }
+
+    /// Lists all of the server's prompts, following cursors across pages
+    pub async fn list_all_prompts(&mut self) -> Result<Vec<original::Prompt>, McpError> {
+        let mut prompts = Vec::new();
+        let mut cursor = None;
+        for _ in 0..self.max_iterations {
+            let page = self.list_prompts(cursor).await?;
+            prompts.extend(page.prompts);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(prompts),
+            }
+        }
+        Err(McpError::TooManyPages(self.max_iterations))
+    }
+
+    /// Sends a request to the server and waits for its result
+    async fn request<R: DeserializeOwned>(&self, request: ClientRequest) -> Result<R, McpError> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        // Hold the transport until the response arrives, so concurrent
+        // requests can't steal each other's responses
+        let mut transport = self.transport.lock().await;
+        let msg: OutgoingMessage = Message::Request {
+            jsonrpc: "2.0".into(),
+            id: RequestId::from(id),
+            request,
+        };
+        transport.send(&serde_json::to_vec(&msg)?).await?;
+        loop {
+            let buf = transport.recv().await?;
+            // An empty read means the server closed the transport
+            if buf.is_empty() {
+                return Err(McpError::Disconnected);
+            }
+            match serde_json::from_slice::<IncomingMessage>(&buf)? {
+                Message::Response {
+                    id: RequestId::Integer(response_id),
+                    result,
+                    ..
+                } if response_id == id => return Ok(serde_json::from_value(result)?),
+                Message::Error(err) if matches!(err.id, RequestId::Integer(error_id) if error_id == id) =>
+                {
+                    return Err(McpError::Rpc {
+                        code: err.error.code,
+                        message: err.error.message,
+                    });
+                }
+                msg => tracing::debug!(?msg, "ignoring message while awaiting a response"),
+            }
+        }
+    }
+
+    /// Sends a notification to the server
+    async fn notify(&self, notification: ClientNotification) -> Result<(), McpError> {
+        let msg: OutgoingMessage = Message::Notification {
+            jsonrpc: "2.0".into(),
+            notification,
+        };
+        let serialized = serde_json::to_vec(&msg)?;
+        Ok(self.transport.lock().await.send(&serialized).await?)
+    }
+}
+
+#[cfg(feature = "http-sse-client")]
+impl MCPClient<crate::transport::HttpSseClientTransport> {
+    /// Connects to a server speaking HTTP with Server-Sent Events, whose
+    /// event stream is at `<base_url>/sse`
+    pub async fn new_http_sse(base_url: &str, name: &str, version: &str) -> Result<Self, McpError> {
+        let transport = crate::transport::HttpSseClientTransport::connect(base_url).await?;
+        Ok(Self::new(transport, name, version))
+    }
+}
+
+impl<T> MCPClient<T>
+where
+    T: Transport + Send + 'static,
+{
+    /// Spawns a task that pings the server every `interval`. If the server
+    /// fails to answer within `timeout`, the connection is considered dead
+    /// and the shutdown token is cancelled.
+    pub fn start_keepalive(
+        self: &Arc<Self>,
+        interval: Duration,
+        timeout: Duration,
+    ) -> JoinHandle<()> {
+        let client = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = client.shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(interval) => {}
+                }
+                match tokio::time::timeout(timeout, client.ping()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => {
+                        tracing::warn!(%err, "keepalive ping failed");
+                        client.shutdown.cancel();
+                        break;
+                    }
+                    Err(_) => {
+                        tracing::warn!(?timeout, "keepalive ping timed out");
+                        client.shutdown.cancel();
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,156 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::io;
+
+/// JSON-RPC error code for a message that is not valid JSON
+pub const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for JSON that is not a valid request
+pub const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code for a method that does not exist or is not available
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for invalid method parameters
+pub const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code for an internal error
+pub const INTERNAL_ERROR: i64 = -32603;
+/// JSON-RPC error code for a generic server error
+pub const SERVER_ERROR: i64 = -32000;
+/// MCP error code for a request naming a resource that does not exist
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// Structured detail attached to a JSON-RPC error response, letting clients
+/// inspect why a request failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum McpErrorData {
+    /// The request or response failed validation
+    ValidationErrors(Vec<String>),
+    /// No resource exists with the requested URI
+    ResourceNotFound { uri: String },
+    /// No tool exists with the requested name
+    ToolNotFound { name: String },
+    /// No prompt exists with the requested name
+    PromptNotFound { name: String },
+    /// The server failed while handling the request
+    InternalError { details: String },
+}
+
+/// Errors that can occur while speaking MCP
+#[derive(Debug)]
+pub enum McpError {
+    /// The underlying transport failed
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// A message could not be serialized or deserialized
+    Serialization(serde_json::Error),
+    /// Saved server state could not be encoded or decoded
+    #[cfg(feature = "server")]
+    State(postcard::Error),
+    /// Expected a request, but got a different kind of message
+    NotARequest,
+    /// Expected a notification, but got a different kind of message
+    NotANotification,
+    /// Expected a response, but got a different kind of message
+    NotAResponse,
+    /// The peer did not advertise the capability a request requires
+    CapabilityNotAvailable(&'static str),
+    /// The peer answered a request with a JSON-RPC error
+    Rpc { code: i64, message: String },
+    /// The connection closed before a response arrived
+    Disconnected,
+    /// The peer took too long to respond
+    Timeout,
+    /// A tool was called without a required argument
+    MissingArgument(String),
+    /// A tool argument has a different JSON type than expected
+    WrongArgumentType {
+        name: String,
+        expected: &'static str,
+    },
+    /// The peer speaks a protocol version this crate does not support
+    UnsupportedVersion(String),
+    /// The peer kept returning further pages of a list past the limit
+    TooManyPages(usize),
+}
+
+impl fmt::Display for McpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "transport error: {err}"),
+            Self::Serialization(err) => write!(f, "serialization error: {err}"),
+            #[cfg(feature = "server")]
+            Self::State(err) => write!(f, "invalid server state: {err}"),
+            Self::NotARequest => write!(f, "message is not a request"),
+            Self::NotANotification => write!(f, "message is not a notification"),
+            Self::NotAResponse => write!(f, "message is not a response"),
+            Self::CapabilityNotAvailable(capability) => {
+                write!(f, "peer does not support the {capability} capability")
+            }
+            Self::Rpc { code, message } => write!(f, "error {code}: {message}"),
+            Self::Disconnected => write!(f, "connection closed"),
+            Self::Timeout => write!(f, "timed out waiting for a response"),
+            Self::MissingArgument(name) => write!(f, "missing argument {name}"),
+            Self::WrongArgumentType { name, expected } => {
+                write!(f, "argument {name} should be {expected}")
+            }
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported protocol version {version}")
+            }
+            Self::TooManyPages(limit) => write!(f, "list still incomplete after {limit} pages"),
+        }
+    }
+}
+
+impl core::error::Error for McpError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => Some(err),
+            #[cfg(feature = "std")]
+            Self::Serialization(err) => Some(err),
+            #[cfg(feature = "server")]
+            Self::State(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for McpError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for McpError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Serialization(err)
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<postcard::Error> for McpError {
+    fn from(err: postcard::Error) -> Self {
+        Self::State(err)
+    }
+}
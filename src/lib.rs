@@ -13,10 +13,51 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <http://www.gnu.org/licenses/>.
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+// Lets code generated by `tool_macros`, which names `::mcp`, be used within
+// this crate too
+extern crate self as mcp;
+
+/// Error types
+pub mod error;
 /// High-level representations and schemas for the Model Context Protocol
 pub mod schema;
+/// Schemas for the 2025-03-26 revision of the Model Context Protocol
+pub mod schema_v2025;
+/// Revisions of the Model Context Protocol schema
+pub mod version;
+/// Re-exported for code generated by `tool_macros`
+#[cfg(feature = "server")]
+#[doc(hidden)]
+pub use async_trait;
 /// Derive macro for Tool queries
 pub use tool_macros;
+/// Schemas for types `ToolQuery` can't describe on its own
+#[cfg(feature = "std")]
+pub mod tool_schema;
+/// Caching for expensive resource reads
+#[cfg(feature = "server")]
+pub mod cache;
+/// Client component
+#[cfg(feature = "client")]
+pub mod client;
+/// Prometheus metrics for servers
+#[cfg(feature = "metrics")]
+pub mod metrics;
 /// Server component
 #[cfg(feature = "server")]
 pub mod server;
+/// Helpers for testing tools, prompts and servers
+#[cfg(feature = "testing")]
+pub mod testing;
+/// Transports carrying messages between client and server
+#[cfg(any(feature = "server", feature = "client"))]
+pub mod transport;
+/// TypeScript bindings for a server's tools, resources and prompts
+#[cfg(feature = "server")]
+pub mod typescript;
+/// Watching resources for changes
+#[cfg(feature = "watch")]
+pub mod watch;
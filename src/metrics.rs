@@ -0,0 +1,128 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+
+/// Prometheus metrics collected by a server
+pub struct ServerMetrics {
+    registry: Registry,
+    requests_received: IntCounterVec,
+    responses_sent: IntCounterVec,
+    errors: IntCounterVec,
+    tool_call_duration: HistogramVec,
+    resource_read_duration: HistogramVec,
+}
+
+impl ServerMetrics {
+    /// Constructor, registering every metric in a fresh registry
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let requests_received = IntCounterVec::new(
+            Opts::new("mcp_requests_received_total", "Requests received"),
+            &["method"],
+        )
+        .unwrap();
+        let responses_sent = IntCounterVec::new(
+            Opts::new("mcp_responses_sent_total", "Responses sent"),
+            &["method"],
+        )
+        .unwrap();
+        let errors = IntCounterVec::new(
+            Opts::new("mcp_errors_total", "Error responses sent"),
+            &["code"],
+        )
+        .unwrap();
+        let tool_call_duration = HistogramVec::new(
+            HistogramOpts::new("mcp_tool_call_duration_seconds", "Time spent calling tools"),
+            &["tool"],
+        )
+        .unwrap();
+        let resource_read_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_resource_read_duration_seconds",
+                "Time spent reading resources",
+            ),
+            &["uri"],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(requests_received.clone()))
+            .unwrap();
+        registry.register(Box::new(responses_sent.clone())).unwrap();
+        registry.register(Box::new(errors.clone())).unwrap();
+        registry
+            .register(Box::new(tool_call_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(resource_read_duration.clone()))
+            .unwrap();
+        Self {
+            registry,
+            requests_received,
+            responses_sent,
+            errors,
+            tool_call_duration,
+            resource_read_duration,
+        }
+    }
+
+    /// Counts a request received from the client
+    pub fn request_received(&self, method: &str) {
+        self.requests_received.with_label_values(&[method]).inc();
+    }
+
+    /// Counts a response sent to the client
+    pub fn response_sent(&self, method: &str) {
+        self.responses_sent.with_label_values(&[method]).inc();
+    }
+
+    /// Counts an error response sent to the client
+    pub fn error_sent(&self, code: i64) {
+        self.errors.with_label_values(&[&code.to_string()]).inc();
+    }
+
+    /// Records how long a tool call took
+    pub fn observe_tool_call(&self, tool: &str, duration: Duration) {
+        self.tool_call_duration
+            .with_label_values(&[tool])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Records how long a resource read took
+    pub fn observe_resource_read(&self, uri: &str, duration: Duration) {
+        self.resource_read_duration
+            .with_label_values(&[uri])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Renders every metric in the Prometheus text format
+    pub fn text(&self) -> String {
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .expect("metrics always encode");
+        String::from_utf8(buf).expect("Prometheus text format is UTF-8")
+    }
+}
+
+impl Default for ServerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -13,38 +13,261 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <http://www.gnu.org/licenses/>.
+use crate::error::McpError;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "validate")]
 use serde_valid::Validate;
+// Without std there is no hasher, so maps are ordered instead
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap as HashMap, string::String};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 /// MCP Protocol version
 pub const VERSION: &str = "2024-11-05";
 
+/// Opaque token used to represent a cursor for pagination
+pub type Cursor = String;
+
+/// Capabilities a client may support. This replaces the generated type so
+/// that an empty capability object, such as `"sampling": {}`, can be told
+/// apart from an absent one.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ClientCapabilities {
+    /// Experimental, non-standard capabilities that the client supports
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    pub experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    /// Present if the client supports listing roots
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub roots: Option<RootsCapability>,
+    /// Present if the client supports sampling from an LLM
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sampling: Option<SamplingCapability>,
+}
+
+/// Client support for listing roots
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RootsCapability {
+    /// Whether the client will notify the server when its roots change
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "listChanged",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub list_changed: Option<bool>,
+}
+
+/// Client support for sampling, which currently has no options
+pub type SamplingCapability = serde_json::Map<String, serde_json::Value>;
+
+/// Capabilities a server may support. Like [`ClientCapabilities`], this
+/// replaces the generated type so that an empty capability object, such as
+/// `"logging": {}`, can be told apart from an absent one.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ServerCapabilities {
+    /// Experimental, non-standard capabilities that the server supports
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    pub experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    /// Present if the server can send log messages to the client
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub logging: Option<LoggingCapability>,
+    /// Present if the server can complete arguments. Added in 2025-03-26.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub completions: Option<CompletionsCapability>,
+    /// Present if the server offers prompt templates
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub prompts: Option<PromptsCapability>,
+    /// Present if the server offers resources
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub resources: Option<ResourcesCapability>,
+    /// Present if the server offers tools
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tools: Option<ToolsCapability>,
+}
+
+/// Server support for logging, which currently has no options
+pub type LoggingCapability = serde_json::Map<String, serde_json::Value>;
+/// Server support for completions, which currently has no options
+pub type CompletionsCapability = serde_json::Map<String, serde_json::Value>;
+
+/// Server support for prompt templates
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PromptsCapability {
+    /// Whether the server will notify the client when its prompts change
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "listChanged",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub list_changed: Option<bool>,
+}
+
+/// Server support for resources
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ResourcesCapability {
+    /// Whether the server will notify the client when its resources change
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "listChanged",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub list_changed: Option<bool>,
+    /// Whether the client can subscribe to updates to a resource
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub subscribe: Option<bool>,
+}
+
+/// Server support for tools
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ToolsCapability {
+    /// Whether the server will notify the client when its tools change
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "listChanged",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub list_changed: Option<bool>,
+}
+
+/// Builds [`ServerCapabilities`], which advertise nothing until told to
+#[derive(Clone, Debug, Default)]
+pub struct ServerCapabilitiesBuilder {
+    capabilities: ServerCapabilities,
+}
+
+impl ServerCapabilitiesBuilder {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advertises tools, and whether the client is told when they change
+    pub fn with_tools(mut self, list_changed: bool) -> Self {
+        self.capabilities.tools = Some(ToolsCapability {
+            list_changed: Some(list_changed),
+        });
+        self
+    }
+
+    /// Advertises resources, whether the client is told when they change,
+    /// and whether it can subscribe to updates
+    pub fn with_resources(mut self, list_changed: bool, subscribe: bool) -> Self {
+        self.capabilities.resources = Some(ResourcesCapability {
+            list_changed: Some(list_changed),
+            subscribe: Some(subscribe),
+        });
+        self
+    }
+
+    /// Advertises prompts, and whether the client is told when they change
+    pub fn with_prompts(mut self, list_changed: bool) -> Self {
+        self.capabilities.prompts = Some(PromptsCapability {
+            list_changed: Some(list_changed),
+        });
+        self
+    }
+
+    /// Advertises sending log messages
+    pub fn with_logging(mut self) -> Self {
+        self.capabilities.logging = Some(LoggingCapability::new());
+        self
+    }
+
+    /// Advertises experimental, non-standard capabilities
+    pub fn with_experimental(
+        mut self,
+        experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    ) -> Self {
+        self.capabilities.experimental = experimental;
+        self
+    }
+
+    /// Returns the capabilities
+    pub fn build(self) -> ServerCapabilities {
+        self.capabilities
+    }
+}
+
 /// Encapsulates anything that will be sent from a particular side
-#[derive(Debug, Deserialize, Serialize, Validate)]
-#[serde(untagged)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "validate", derive(Validate))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Message<RQ, RS, N> {
     /// JSONRPC Request
     Request {
-        #[validate(custom = validate_jsonrpc_version)]
+        #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
         #[doc(hidden)]
         jsonrpc: String,
-        id: original::zerocopy::RequestId,
-        #[serde(flatten)]
+        id: original::RequestId,
+        #[cfg_attr(feature = "serde", serde(flatten))]
         request: RQ,
     },
     /// JSONRPC Notification
     Notification {
-        #[validate(custom = validate_jsonrpc_version)]
+        #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
         #[doc(hidden)]
         jsonrpc: String,
-        #[serde(flatten)]
+        #[cfg_attr(feature = "serde", serde(flatten))]
         notification: N,
     },
     /// JSONRPC Error
-    Error(#[validate(custom = validate_jsonrpc_error)] original::JsonrpcError),
+    Error(
+        #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_error))]
+        original::JsonrpcError,
+    ),
     /// JSONRPC Response
     Response {
-        #[validate(custom = validate_jsonrpc_version)]
+        #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
         #[doc(hidden)]
         jsonrpc: String,
         id: original::RequestId,
@@ -57,7 +280,125 @@ pub type ClientMessage = Message<ClientRequest, ClientResult, ClientNotification
 /// A message sent by an MCP server
 pub type ServerMessage = Message<ServerRequest, ServerResult, ServerNotification>;
 
+impl TryFrom<ClientMessage> for (original::RequestId, ClientRequest) {
+    type Error = McpError;
+
+    fn try_from(msg: ClientMessage) -> Result<Self, Self::Error> {
+        match msg {
+            Message::Request { id, request, .. } => Ok((id, request)),
+            _ => Err(McpError::NotARequest),
+        }
+    }
+}
+
+impl TryFrom<ClientMessage> for ClientNotification {
+    type Error = McpError;
+
+    fn try_from(msg: ClientMessage) -> Result<Self, Self::Error> {
+        match msg {
+            Message::Notification { notification, .. } => Ok(notification),
+            _ => Err(McpError::NotANotification),
+        }
+    }
+}
+
+impl TryFrom<ServerMessage> for ServerResult {
+    type Error = McpError;
+
+    fn try_from(msg: ServerMessage) -> Result<Self, Self::Error> {
+        match msg {
+            Message::Response { result, .. } => Ok(result),
+            _ => Err(McpError::NotAResponse),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<serde_json::Value> for ClientMessage {
+    type Error = McpError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(McpError::Serialization)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<serde_json::Value> for ServerMessage {
+    type Error = McpError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(McpError::Serialization)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ClientMessage> for serde_json::Value {
+    fn from(msg: ClientMessage) -> Self {
+        serde_json::to_value(msg).expect("messages always serialize to JSON")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ServerMessage> for serde_json::Value {
+    fn from(msg: ServerMessage) -> Self {
+        serde_json::to_value(msg).expect("messages always serialize to JSON")
+    }
+}
+
+// The generated type already has `From<i64>`, and `TryFrom` impls for strings
+// that rule out `From<&str>` and `From<String>`. Those go through `FromStr`,
+// which can't tell "1" from 1, so give tests explicit constructors instead.
+impl original::RequestId {
+    /// Creates a string request ID
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        Self::String(s.into())
+    }
+
+    /// Creates an integer request ID
+    pub fn from_int(n: i64) -> Self {
+        Self::Integer(n)
+    }
+}
+
+/// A message from a client speaking any supported protocol version
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq)]
+pub enum VersionedClientMessage {
+    V2024(ClientMessage),
+    V2025(crate::schema_v2025::ClientMessage),
+}
+
+/// Parses a message from a client speaking any supported protocol version.
+///
+/// Only `initialize` carries a `protocolVersion`. Other messages are parsed
+/// as the oldest version that accepts them, so callers that know the
+/// negotiated version should prefer parsing with it directly.
+#[cfg(feature = "serde")]
+pub fn parse_versioned(bytes: &[u8]) -> Result<VersionedClientMessage, McpError> {
+    let value: serde_json::Value = serde_json::from_slice(bytes)?;
+    let protocol_version = value
+        .pointer("/params/protocolVersion")
+        .and_then(serde_json::Value::as_str);
+    match protocol_version {
+        Some(VERSION) => Ok(VersionedClientMessage::V2024(serde_json::from_value(
+            value,
+        )?)),
+        Some(crate::schema_v2025::VERSION) => Ok(VersionedClientMessage::V2025(
+            serde_json::from_value(value)?,
+        )),
+        Some(version) => Err(McpError::UnsupportedVersion(version.into())),
+        None => match serde_json::from_value(value.clone()) {
+            Ok(msg) => Ok(VersionedClientMessage::V2024(msg)),
+            Err(_) => Ok(VersionedClientMessage::V2025(serde_json::from_value(
+                value,
+            )?)),
+        },
+    }
+}
+
 /// Custom serde validation function to make sure jsonrpc is the correct version
+#[cfg(feature = "validate")]
 fn validate_jsonrpc_version(val: &str) -> Result<(), serde_valid::validation::Error> {
     if val == "2.0" {
         Ok(())
@@ -69,6 +410,7 @@ fn validate_jsonrpc_version(val: &str) -> Result<(), serde_valid::validation::Er
 }
 
 /// Custom serde validation function to make sure jsonrpc is the correct version
+#[cfg(feature = "validate")]
 fn validate_jsonrpc_error(
     err: &original::JsonrpcError,
 ) -> Result<(), serde_valid::validation::Error> {
@@ -76,40 +418,42 @@ fn validate_jsonrpc_error(
 }
 
 /// Request made by the client
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(tag = "method", content = "params")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
 pub enum ClientRequest {
-    #[serde(rename = "initialize")]
+    #[cfg_attr(feature = "serde", serde(rename = "initialize"))]
     Initialize(original::InitializeRequestParams),
-    #[serde(rename = "ping")]
+    #[cfg_attr(feature = "serde", serde(rename = "ping"))]
     Ping(original::PingRequestParams),
-    #[serde(rename = "resources/list")]
+    #[cfg_attr(feature = "serde", serde(rename = "resources/list"))]
     ListResources(original::ListResourcesRequestParams),
-    #[serde(rename = "resources/templates/list")]
+    #[cfg_attr(feature = "serde", serde(rename = "resources/templates/list"))]
     ListResourceTemplates(original::ListResourceTemplatesRequestParams),
-    #[serde(rename = "resources/read")]
+    #[cfg_attr(feature = "serde", serde(rename = "resources/read"))]
     ReadResource(original::ReadResourceRequestParams),
-    #[serde(rename = "resources/subscribe")]
+    #[cfg_attr(feature = "serde", serde(rename = "resources/subscribe"))]
     Subscribe(original::SubscribeRequestParams),
-    #[serde(rename = "resources/unsubscribe")]
+    #[cfg_attr(feature = "serde", serde(rename = "resources/unsubscribe"))]
     Unsubscribe(original::UnsubscribeRequestParams),
-    #[serde(rename = "prompts/list")]
+    #[cfg_attr(feature = "serde", serde(rename = "prompts/list"))]
     ListPrompts(original::ListPromptsRequestParams),
-    #[serde(rename = "prompts/get")]
+    #[cfg_attr(feature = "serde", serde(rename = "prompts/get"))]
     GetPrompt(original::GetPromptRequestParams),
-    #[serde(rename = "tools/list")]
+    #[cfg_attr(feature = "serde", serde(rename = "tools/list"))]
     ListTools(original::ListToolsRequestParams),
-    #[serde(rename = "tools/call")]
+    #[cfg_attr(feature = "serde", serde(rename = "tools/call"))]
     CallTool(original::CallToolRequestParams),
-    #[serde(rename = "logging/setlevel")]
+    #[cfg_attr(feature = "serde", serde(rename = "logging/setLevel"))]
     SetLevel(original::SetLevelRequestParams),
-    #[serde(rename = "completion/complete")]
+    #[cfg_attr(feature = "serde", serde(rename = "completion/complete"))]
     Complete(original::CompleteRequestParams),
 }
 
 /// Result sent by the client
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(untagged)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum ClientResult {
     Result(original::ResultData),
     CreateMessage(original::CreateMessageResult),
@@ -117,33 +461,40 @@ pub enum ClientResult {
 }
 
 /// Notification sent by the client
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(tag = "method", content = "params")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
 pub enum ClientNotification {
-    #[serde(rename = "notifications/cancelled")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/cancelled"))]
     Cancelled(original::CancelledNotificationParams),
-    #[serde(rename = "notifications/initialized")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/initialized"))]
     Initialized(original::InitializedNotificationParams),
-    #[serde(rename = "notifications/progress")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/progress"))]
     Progress(original::ProgressNotificationParams),
-    #[serde(rename = "notifications/roots/list_changed")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/roots/list_changed"))]
     RootsListChanged(original::RootsListChangedNotificationParams),
+    /// Not in the spec, which only defines `logging/setLevel` as a request,
+    /// but accepted from clients that send it without expecting a response
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/logging/setLevel"))]
+    SetLevel(original::SetLevelRequestParams),
 }
 
 /// Request made by the server
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(tag = "method", content = "params")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
 pub enum ServerRequest {
-    #[serde(rename = "ping")]
+    #[cfg_attr(feature = "serde", serde(rename = "ping"))]
     Ping(original::PingRequestParams),
-    #[serde(rename = "sampling/createMessage")]
+    #[cfg_attr(feature = "serde", serde(rename = "sampling/createMessage"))]
     CreateMessage(original::CreateMessageRequestParams),
-    #[serde(rename = "roots/list")]
+    #[cfg_attr(feature = "serde", serde(rename = "roots/list"))]
     ListRoots(original::ListRootsRequestParams),
 }
 /// Result sent by the server
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(untagged)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum ServerResult {
     Empty(original::ResultData),
     Initialize(original::InitializeResult),
@@ -158,37 +509,50 @@ pub enum ServerResult {
 }
 
 /// Notification sent by the server
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(tag = "method", content = "params")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
 pub enum ServerNotification {
-    #[serde(rename = "notifications/cancelled")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/cancelled"))]
     Cancelled(original::CancelledNotificationParams),
-    #[serde(rename = "notifications/progress")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/progress"))]
     Progress(original::ProgressNotificationParams),
-    #[serde(rename = "notifications/resources/list_changed")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "notifications/resources/list_changed")
+    )]
     ResourceListChanged(original::ResourceListChangedNotificationParams),
-    #[serde(rename = "notifications/resources/updated")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/resources/updated"))]
     ResourceUpdated(original::ResourceUpdatedNotificationParams),
-    #[serde(rename = "notifications/prompts/list_changed")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "notifications/prompts/list_changed")
+    )]
     PromptListChanged(original::PromptListChangedNotificationParams),
-    #[serde(rename = "notifications/tools/list_changed")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/tools/list_changed"))]
     ToolListChanged(original::ToolListChangedNotificationParams),
-    #[serde(rename = "notifications/message")]
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/message"))]
     LoggingMessage(original::LoggingMessageNotificationParams),
 }
 /// Zero-copy versions of high-level MCP schema
+#[cfg(feature = "serde")]
 pub mod zerocopy {
-    use super::{original::zerocopy as original, validate_jsonrpc_version};
+    use super::original::zerocopy as original;
+    #[cfg(feature = "validate")]
+    use super::validate_jsonrpc_version;
+    use crate::error::McpError;
     use serde::{Deserialize, Serialize};
+    #[cfg(feature = "validate")]
     use serde_valid::Validate;
 
     /// Encapsulates anything that will be sent from a particular side
-    #[derive(Debug, Deserialize, Serialize, Validate)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr(feature = "validate", derive(Validate))]
     #[serde(untagged)]
     pub enum Message<'a, RQ, RS, N> {
         /// JSONRPC Request
         Request {
-            #[validate(custom = validate_jsonrpc_version)]
+            #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
             #[doc(hidden)]
             jsonrpc: &'a str,
             id: original::RequestId,
@@ -197,7 +561,7 @@ pub mod zerocopy {
         },
         /// JSONRPC Notification
         Notification {
-            #[validate(custom = validate_jsonrpc_version)]
+            #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
             #[doc(hidden)]
             jsonrpc: &'a str,
             #[serde(flatten)]
@@ -206,12 +570,12 @@ pub mod zerocopy {
         /// JSONRPC Error
         Error(
             #[serde(borrow)]
-            #[validate(custom = validate_jsonrpc_error)]
+            #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_error))]
             original::JsonrpcError<'a>,
         ),
         /// JSONRPC Response
         Response {
-            #[validate(custom = validate_jsonrpc_version)]
+            #[cfg_attr(feature = "validate", validate(custom = validate_jsonrpc_version))]
             #[doc(hidden)]
             jsonrpc: &'a str,
             id: original::RequestId,
@@ -226,7 +590,41 @@ pub mod zerocopy {
     pub type ServerMessage<'a> =
         Message<'a, ServerRequest<'a>, ServerResult<'a>, ServerNotification<'a>>;
 
+    impl<'a> TryFrom<ClientMessage<'a>> for (original::RequestId, ClientRequest<'a>) {
+        type Error = McpError;
+
+        fn try_from(msg: ClientMessage<'a>) -> Result<Self, Self::Error> {
+            match msg {
+                Message::Request { id, request, .. } => Ok((id, request)),
+                _ => Err(McpError::NotARequest),
+            }
+        }
+    }
+
+    impl<'a> TryFrom<ClientMessage<'a>> for ClientNotification<'a> {
+        type Error = McpError;
+
+        fn try_from(msg: ClientMessage<'a>) -> Result<Self, Self::Error> {
+            match msg {
+                Message::Notification { notification, .. } => Ok(notification),
+                _ => Err(McpError::NotANotification),
+            }
+        }
+    }
+
+    impl<'a> TryFrom<ServerMessage<'a>> for ServerResult<'a> {
+        type Error = McpError;
+
+        fn try_from(msg: ServerMessage<'a>) -> Result<Self, Self::Error> {
+            match msg {
+                Message::Response { result, .. } => Ok(result),
+                _ => Err(McpError::NotAResponse),
+            }
+        }
+    }
+
     /// Custom serde validation function to make sure jsonrpc is the correct version
+    #[cfg(feature = "validate")]
     fn validate_jsonrpc_error<'a>(
         err: &original::JsonrpcError<'a>,
     ) -> Result<(), serde_valid::validation::Error> {
@@ -234,7 +632,7 @@ pub mod zerocopy {
     }
 
     /// Request made by the client
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(tag = "method", content = "params")]
     pub enum ClientRequest<'a> {
         #[serde(rename = "initialize")]
@@ -259,14 +657,35 @@ pub mod zerocopy {
         ListTools(#[serde(borrow)] original::ListToolsRequestParams<'a>),
         #[serde(rename = "tools/call")]
         CallTool(#[serde(borrow)] original::CallToolRequestParams<'a>),
-        #[serde(rename = "logging/setlevel")]
+        #[serde(rename = "logging/setLevel")]
         SetLevel(original::SetLevelRequestParams),
         #[serde(rename = "completion/complete")]
         Complete(#[serde(borrow)] original::CompleteRequestParams<'a>),
     }
 
+    impl ClientRequest<'_> {
+        /// JSONRPC method name of the request
+        pub fn method(&self) -> &'static str {
+            match self {
+                Self::Initialize(_) => "initialize",
+                Self::Ping(_) => "ping",
+                Self::ListResources(_) => "resources/list",
+                Self::ListResourceTemplates(_) => "resources/templates/list",
+                Self::ReadResource(_) => "resources/read",
+                Self::Subscribe(_) => "resources/subscribe",
+                Self::Unsubscribe(_) => "resources/unsubscribe",
+                Self::ListPrompts(_) => "prompts/list",
+                Self::GetPrompt(_) => "prompts/get",
+                Self::ListTools(_) => "tools/list",
+                Self::CallTool(_) => "tools/call",
+                Self::SetLevel(_) => "logging/setLevel",
+                Self::Complete(_) => "completion/complete",
+            }
+        }
+    }
+
     /// Result sent by the client
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(untagged)]
     pub enum ClientResult<'a> {
         Result(original::ResultData),
@@ -275,7 +694,7 @@ pub mod zerocopy {
     }
 
     /// Notification sent by the client
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(tag = "method", content = "params")]
     pub enum ClientNotification<'a> {
         #[serde(rename = "notifications/cancelled")]
@@ -286,10 +705,27 @@ pub mod zerocopy {
         Progress(original::ProgressNotificationParams),
         #[serde(rename = "notifications/roots/list_changed")]
         RootsListChanged(original::RootsListChangedNotificationParams),
+        /// Not in the spec, which only defines `logging/setLevel` as a request,
+        /// but accepted from clients that send it without expecting a response
+        #[serde(rename = "notifications/logging/setLevel")]
+        SetLevel(original::SetLevelRequestParams),
+    }
+
+    impl ClientNotification<'_> {
+        /// JSONRPC method name of the notification
+        pub fn method(&self) -> &'static str {
+            match self {
+                Self::Cancelled(_) => "notifications/cancelled",
+                Self::Initialized(_) => "notifications/initialized",
+                Self::Progress(_) => "notifications/progress",
+                Self::RootsListChanged(_) => "notifications/roots/list_changed",
+                Self::SetLevel(_) => "notifications/logging/setLevel",
+            }
+        }
     }
 
     /// Request made by the server
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(tag = "method", content = "params")]
     pub enum ServerRequest<'a> {
         #[serde(rename = "ping")]
@@ -300,7 +736,7 @@ pub mod zerocopy {
         ListRoots(original::ListRootsRequestParams),
     }
     /// Result sent by the server
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(untagged)]
     pub enum ServerResult<'a> {
         Empty(original::ResultData),
@@ -316,7 +752,7 @@ pub mod zerocopy {
     }
 
     /// Notification sent by the server
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
     #[serde(tag = "method", content = "params")]
     pub enum ServerNotification<'a> {
         #[serde(rename = "notifications/cancelled")]
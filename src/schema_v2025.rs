@@ -0,0 +1,88 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// MCP Protocol version
+pub const VERSION: &str = "2025-03-26";
+
+/// A message sent by an MCP client. The envelope is unchanged from the
+/// previous version, so it is shared.
+pub type ClientMessage = crate::schema::Message<ClientRequest, ClientResult, ClientNotification>;
+
+/// Request made by the client
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
+pub enum ClientRequest {
+    #[cfg_attr(feature = "serde", serde(rename = "initialize"))]
+    Initialize(original::InitializeRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "ping"))]
+    Ping(original::PingRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "resources/list"))]
+    ListResources(original::ListResourcesRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "resources/templates/list"))]
+    ListResourceTemplates(original::ListResourceTemplatesRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "resources/read"))]
+    ReadResource(original::ReadResourceRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "resources/subscribe"))]
+    Subscribe(original::SubscribeRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "resources/unsubscribe"))]
+    Unsubscribe(original::UnsubscribeRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "prompts/list"))]
+    ListPrompts(original::ListPromptsRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "prompts/get"))]
+    GetPrompt(original::GetPromptRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "tools/list"))]
+    ListTools(original::ListToolsRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "tools/call"))]
+    CallTool(original::CallToolRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "logging/setLevel"))]
+    SetLevel(original::SetLevelRequestParams),
+    #[cfg_attr(feature = "serde", serde(rename = "completion/complete"))]
+    Complete(original::CompleteRequestParams),
+}
+
+/// Result sent by the client
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum ClientResult {
+    Result(original::ResultData),
+    CreateMessage(original::CreateMessageResult),
+    ListRoots(original::ListRootsResult),
+}
+
+/// Notification sent by the client
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "method", content = "params"))]
+pub enum ClientNotification {
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/cancelled"))]
+    Cancelled(original::CancelledNotificationParams),
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/initialized"))]
+    Initialized(original::InitializedNotificationParams),
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/progress"))]
+    Progress(original::ProgressNotificationParams),
+    #[cfg_attr(feature = "serde", serde(rename = "notifications/roots/list_changed"))]
+    RootsListChanged(original::RootsListChangedNotificationParams),
+}
+
+/// MCP Schemas imported and converted from the official MCP specification
+pub mod original {
+    include!(concat!(env!("OUT_DIR"), "/schema_2025_03_26.rs"));
+}
@@ -14,30 +14,241 @@
 // You should have received a copy of the GNU General Public License along with
 // this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::schema::original::zerocopy::{
-    ClientNotification, Implementation, InitializeRequestParams, InitializeResult,
-    ListPromptsResult, ListResourcesResult, ListToolsResult, RequestId, ResultData,
-    ServerCapabilities, ServerCapabilitiesPrompts, ServerCapabilitiesResources,
-    ServerCapabilitiesTools,
+use crate::error::{
+    McpError, McpErrorData, INTERNAL_ERROR, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND,
+    PARSE_ERROR, RESOURCE_NOT_FOUND, SERVER_ERROR,
 };
-use crate::schema::zerocopy::{ClientMessage, ClientRequest, Message, ServerMessage, ServerResult};
+#[cfg(feature = "metrics")]
+use crate::metrics::ServerMetrics;
+use crate::schema::original::{
+    self,
+    zerocopy::{
+        CallToolRequestParams, CompleteRequestParams, CompleteRequestParamsRef,
+        GetPromptRequestParams, InitializeRequestParams, LoggingLevel, ReadResourceRequestParams,
+        RequestId, SetLevelRequestParams, SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+};
+use crate::schema::zerocopy::{ClientMessage, ClientNotification, ClientRequest, Message};
+use crate::schema::{
+    ClientCapabilities, PromptsCapability, ResourcesCapability, ServerCapabilities,
+    ServerCapabilitiesBuilder, ServerMessage, ServerNotification, ServerRequest, ServerResult,
+    ToolsCapability,
+};
+pub use crate::transport::{StdioTransport, Transport};
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::io;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Stdin, Stdout};
-use tokio::sync::RwLock;
-use yoke::Yoke;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_valid::Validate;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock, RwLockReadGuard, Semaphore};
+
+/// Number of [`ConnectionEvent`]s buffered for slow subscribers
+const EVENT_CAPACITY: usize = 64;
+/// Number of items returned per page of a list request
+const PAGE_SIZE: usize = 50;
+/// Most values a completion may return, per the spec
+const MAX_COMPLETIONS: usize = 100;
+
+/// A message sent by the server. Unlike incoming messages, these are owned, as
+/// their contents are produced by tools and resources rather than borrowed
+/// from the client.
+pub type OutgoingMessage = ServerMessage;
 
 /// an MCP server, capable of responding to requests
 pub struct MCPServer<T: Transport> {
-    transport: T,
+    transport: Mutex<T>,
     name: String,
     version: String,
     instructions: Option<String>,
-    tools: RwLock<HashMap<String, Box<dyn Tool>>>,
-    resources: RwLock<HashMap<String, Box<dyn Resource>>>,
-    /// Whether the client is initialized
-    client_initialized: bool,
+    /// Computes instructions for each client, instead of `instructions`
+    instructions_provider: Option<Box<dyn InstructionsProvider>>,
+    tools: Arc<ToolRegistry>,
+    resources: Arc<ResourceRegistry>,
+    prompts: Arc<PromptRegistry>,
+    resource_templates: RwLock<HashMap<String, Box<dyn ResourceTemplate>>>,
+    /// State negotiated with the client, if it has sent `initialize`
+    client: RwLock<Option<ClientState>>,
+    /// URIs of resources the client is subscribed to
+    subscriptions: RwLock<HashSet<String>>,
+    /// Minimum level of log messages the client wants, if it has set one
+    log_level: RwLock<Option<LoggingLevel>>,
+    /// Whether the client's log level also limits the `log` crate
+    sync_log_level: bool,
+    /// Sender for connection lifecycle events
+    events: broadcast::Sender<ConnectionEvent>,
+    /// Sender for changes to the client's resource subscriptions
+    subscription_changed: broadcast::Sender<SubscriptionEvent>,
+    /// Whether to log full, pretty-printed messages
+    debug_mode: bool,
+    /// Whether to print every message to stderr, in debug builds only
+    #[cfg(debug_assertions)]
+    debug_echo: bool,
+    /// Validation applied to every outgoing message
+    validation: ValidationMiddleware,
+    /// Whether tool call logs include argument values, not just their types
+    verbose_logging: bool,
+    /// Receives a log of each tool call, instead of `tracing`
+    call_log_sink: Option<Box<dyn Fn(&CallLog) + Send + Sync>>,
+    /// Policies every incoming message must pass before it is handled
+    validators: Vec<Box<dyn MessageValidator>>,
+    /// Hooks run before and after requests, by method
+    hooks: HookSet,
+    /// Handlers taking over requests from the built-in ones
+    router: RequestRouter,
+    /// Longest to wait for the client to send `initialize` before giving up
+    /// on the connection
+    handshake_timeout: Duration,
+    /// Longest a request may take to handle before it is abandoned
+    request_timeout: Option<Duration>,
+    /// Longest to wait for the client to answer a ping
+    ping_timeout: Duration,
+    /// Number of items returned per page of a list request
+    page_size: usize,
+    /// Called with the request ID and method of each request that times out
+    on_timeout: Option<Box<dyn Fn(RequestId, &str) + Send + Sync>>,
+    /// Capabilities advertised to the client, besides experimental ones
+    capabilities: ServerCapabilities,
+    /// Whether tools, resources and prompts are advertised only when any are
+    /// registered, checked as each client initializes
+    capabilities_from_registry: bool,
+    /// Experimental capabilities advertised to the client
+    experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    /// Tracks subscriptions shared with other servers, if any
+    #[cfg(feature = "watch")]
+    subscription_manager: Option<Arc<crate::watch::ResourceSubscriptionManager>>,
+    /// Source of notifications for every connection, if shared with other
+    /// servers
+    coordinator: Option<Arc<ServerCoordinator>>,
+    /// Queue of server-initiated messages, drained by the run loop
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    outgoing_rx: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+    /// Request counts and latencies
+    #[cfg(feature = "metrics")]
+    metrics: ServerMetrics,
+    /// Server-initiated requests awaiting a response, keyed by request ID
+    pending: Mutex<HashMap<i64, oneshot::Sender<Result<serde_json::Value, McpError>>>>,
+    /// ID of the next server-initiated request
+    next_request_id: AtomicI64,
+    /// When the server was constructed
+    start_time: Instant,
+    /// Number of requests answered
+    requests_handled: AtomicU64,
+    /// When the latest request arrived, if any has
+    last_request_at: RwLock<Option<Instant>>,
+}
+
+/// A snapshot of a server's state, for health checks
+#[derive(Debug, Clone)]
+pub struct ServerHealth {
+    /// Time since the server was constructed
+    pub uptime: Duration,
+    /// Number of requests answered
+    pub requests_handled: u64,
+    /// Number of tools registered
+    pub tools_registered: usize,
+    /// Number of resources registered
+    pub resources_registered: usize,
+    /// Number of prompts registered
+    pub prompts_registered: usize,
+    /// Whether a client has initialized the connection
+    pub client_connected: bool,
+    /// When the latest request arrived, if any has
+    pub last_request_at: Option<Instant>,
+}
+
+/// Protocol transitions observed by an [`MCPServer`]
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    /// The server started listening on its transport
+    ClientConnected,
+    /// The client sent `initialize`
+    ClientInitialized {
+        client_info: original::Implementation,
+    },
+    /// A request was received from the client
+    RequestReceived { method: &'static str, id: RequestId },
+    /// A response was sent to the client
+    ResponseSent { id: RequestId, duration: Duration },
+    /// A notification was received from the client
+    NotificationReceived { method: &'static str },
+    /// The transport was closed by the client
+    ClientDisconnected,
+}
+
+/// A change to the set of resources the client is subscribed to
+#[derive(Debug, Clone)]
+pub enum SubscriptionEvent {
+    /// The client subscribed to a resource
+    Subscribed { uri: String },
+    /// The client unsubscribed from a resource, or disconnected
+    Unsubscribed { uri: String },
+}
+
+/// Information about the connected client, negotiated during initialization
+#[derive(Debug, Clone)]
+pub struct ClientState {
+    /// Name and version of the client
+    pub info: original::Implementation,
+    /// Capabilities advertised by the client
+    pub capabilities: ClientCapabilities,
+    /// Protocol version requested by the client
+    pub protocol_version: String,
+    /// Whether the client has sent `notifications/initialized`
+    pub initialized: bool,
+}
+
+/// Client capabilities a server request may depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientCapabilityFlag {
+    /// The client can sample from an LLM via `sampling/createMessage`
+    Sampling,
+    /// The client can list its roots via `roots/list`
+    Roots,
+    /// The client advertised at least one experimental capability
+    Experimental,
+}
+
+/// The `result` member of a response, extracted without interpreting it
+#[derive(Deserialize)]
+struct RawResult {
+    result: serde_json::Value,
+}
+
+/// Just enough of an incoming message to tell a successful response apart
+/// from everything else, without parsing the rest
+#[derive(Deserialize)]
+struct MessageKind<'a> {
+    #[serde(borrow)]
+    method: Option<&'a str>,
+    id: Option<RequestId>,
+    #[serde(borrow)]
+    result: Option<&'a RawValue>,
+}
+
+/// Connection state as written by [`MCPServer::serialize_state`]
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+    client: Option<SavedClientState>,
+    subscriptions: Vec<String>,
+}
+
+/// [`ClientState`] in a form postcard can round-trip
+#[derive(Serialize, Deserialize)]
+struct SavedClientState {
+    name: String,
+    version: String,
+    protocol_version: String,
+    /// Capabilities are kept as JSON since they contain arbitrary values,
+    /// which postcard cannot deserialize
+    capabilities: String,
+    initialized: bool,
 }
 
 impl<T> MCPServer<T>
@@ -53,215 +264,2846 @@ where
         tools: HashMap<String, Box<dyn Tool>>,
         resources: HashMap<String, Box<dyn Resource>>,
     ) -> Self {
-        Self {
+        Self::new_with_registry(
             transport,
+            name,
+            version,
+            instructions,
+            Arc::new(ToolRegistry::new(tools)),
+            Arc::new(ResourceRegistry::new(resources)),
+            Arc::default(),
+        )
+    }
+
+    /// Constructor for a server sharing its tools, resources and prompts
+    /// with other servers, such as one per connection
+    pub fn new_with_registry(
+        transport: T,
+        name: &str,
+        version: &str,
+        instructions: Option<&str>,
+        tool_registry: Arc<ToolRegistry>,
+        resource_registry: Arc<ResourceRegistry>,
+        prompt_registry: Arc<PromptRegistry>,
+    ) -> Self {
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        Self {
+            transport: Mutex::new(transport),
             name: name.into(),
             version: version.into(),
             instructions: instructions.map(String::from),
-            tools: RwLock::new(tools),
-            resources: RwLock::new(resources),
-            client_initialized: false,
+            instructions_provider: None,
+            tools: tool_registry,
+            resources: resource_registry,
+            prompts: prompt_registry,
+            resource_templates: RwLock::new(HashMap::new()),
+            client: RwLock::new(None),
+            subscriptions: RwLock::new(HashSet::new()),
+            log_level: RwLock::new(None),
+            sync_log_level: false,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            subscription_changed: broadcast::channel(EVENT_CAPACITY).0,
+            debug_mode: false,
+            #[cfg(debug_assertions)]
+            debug_echo: false,
+            validation: ValidationMiddleware::new(),
+            verbose_logging: false,
+            call_log_sink: None,
+            validators: Vec::new(),
+            hooks: HookSet::new(),
+            router: RequestRouter::new(),
+            handshake_timeout: Duration::from_secs(10),
+            request_timeout: None,
+            ping_timeout: Duration::from_secs(10),
+            page_size: PAGE_SIZE,
+            on_timeout: None,
+            capabilities: ServerCapabilitiesBuilder::new()
+                .with_logging()
+                .with_prompts(true)
+                .with_resources(true, true)
+                .with_tools(true)
+                .build(),
+            capabilities_from_registry: false,
+            experimental: HashMap::new(),
+            #[cfg(feature = "watch")]
+            subscription_manager: None,
+            coordinator: None,
+            outgoing,
+            outgoing_rx: Mutex::new(outgoing_rx),
+            #[cfg(feature = "metrics")]
+            metrics: ServerMetrics::new(),
+            pending: Mutex::new(HashMap::new()),
+            next_request_id: AtomicI64::new(0),
+            start_time: Instant::now(),
+            requests_handled: AtomicU64::new(0),
+            last_request_at: RwLock::new(None),
+        }
+    }
+
+    /// Sets whether sent and received messages are logged in full as
+    /// pretty-printed JSON. Off by default.
+    pub fn with_debug_mode(mut self, debug_mode: bool) -> Self {
+        self.debug_mode = debug_mode;
+        self
+    }
+
+    /// Sets whether every message received and sent is printed to stderr,
+    /// pretty-printed. Has no effect in release builds.
+    #[cfg_attr(not(debug_assertions), allow(unused_mut, unused_variables))]
+    pub fn with_debug_echo(mut self, debug_echo: bool) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            self.debug_echo = debug_echo;
+        }
+        self
+    }
+
+    /// Sets whether tool call logs include argument values. Off by default,
+    /// since arguments may contain user data.
+    pub fn with_verbose_logging(mut self, verbose_logging: bool) -> Self {
+        self.verbose_logging = verbose_logging;
+        self
+    }
+
+    /// Sends tool call logs to the given sink instead of `tracing`
+    pub fn with_call_log_sink(mut self, sink: impl Fn(&CallLog) + Send + Sync + 'static) -> Self {
+        self.call_log_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Reports a finished tool call
+    fn log_call(&self, log: CallLog) {
+        match &self.call_log_sink {
+            Some(sink) => sink(&log),
+            None => tracing::info!(
+                tool = %log.tool_name,
+                id = ?log.request_id,
+                duration_ms = log.duration_ms,
+                args = ?log.arg_summary,
+                values = log.arguments.as_ref().map(tracing::field::debug),
+                content_count = log.result_content_count,
+                is_error = log.is_error,
+                "tool called"
+            ),
+        }
+    }
+
+    /// Adds a policy every incoming message must pass before it is handled
+    pub fn with_message_validator(mut self, validator: impl MessageValidator + 'static) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Sets how long to wait for the client to send `initialize` before
+    /// closing the connection. Defaults to 10 seconds.
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    /// Limits how long a request may take to handle. Requests that take
+    /// longer are answered with an error instead.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long [`ping`](Self::ping) waits for the client to answer
+    pub fn with_ping_timeout(mut self, timeout: Duration) -> Self {
+        self.ping_timeout = timeout;
+        self
+    }
+
+    /// Sets how many items are returned per page of a list request. Defaults
+    /// to 50.
+    pub fn with_page_size(mut self, page_size: NonZeroUsize) -> Self {
+        self.page_size = page_size.get();
+        self
+    }
+
+    /// Replaces the capabilities advertised to the client
+    pub fn with_capabilities(mut self, capabilities: ServerCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self.capabilities_from_registry = false;
+        self
+    }
+
+    /// Sets a callback run with the request ID and method of each request
+    /// that times out
+    pub fn with_on_timeout(
+        mut self,
+        on_timeout: impl Fn(RequestId, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_timeout = Some(Box::new(on_timeout));
+        self
+    }
+
+    /// Routes the client's resource subscriptions through a manager shared
+    /// with other servers, which watches the resources for changes
+    #[cfg(feature = "watch")]
+    pub fn with_subscription_manager(
+        mut self,
+        manager: Arc<crate::watch::ResourceSubscriptionManager>,
+    ) -> Self {
+        self.subscription_manager = Some(manager);
+        self
+    }
+
+    /// Sends notifications broadcast through `coordinator` to this server's
+    /// client, alongside the other servers sharing it
+    pub fn with_coordinator(mut self, coordinator: Arc<ServerCoordinator>) -> Self {
+        self.coordinator = Some(coordinator);
+        self
+    }
+
+    /// Sends a notification to the clients of every server sharing this
+    /// one's coordinator, or only to this server's client without one
+    pub async fn broadcast_notification(
+        &self,
+        notification: ServerNotification,
+    ) -> Result<(), McpError> {
+        match &self.coordinator {
+            Some(coordinator) => coordinator.broadcast_notification(notification).await,
+            None => self.notify(notification),
+        }
+    }
+
+    /// Sets whether the level the client sets with `logging/setLevel` also
+    /// becomes the maximum level of the `log` crate, for the whole process.
+    /// Defaults to off.
+    pub fn with_sync_log_level(mut self, sync_log_level: bool) -> Self {
+        self.sync_log_level = sync_log_level;
+        self
+    }
+
+    /// Sets the hooks run before and after requests
+    pub fn with_hooks(mut self, hooks: HookSet) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Sets the router handing requests to other handlers before the
+    /// built-in ones
+    pub fn with_router(mut self, router: RequestRouter) -> Self {
+        self.router = router;
+        self
+    }
+
+    /// Replaces the validation applied to outgoing messages
+    pub fn with_validation(mut self, validation: ValidationMiddleware) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Computes the instructions sent to each client when it initializes,
+    /// replacing any fixed instructions
+    pub fn with_instructions_provider(
+        mut self,
+        provider: impl InstructionsProvider + 'static,
+    ) -> Self {
+        self.instructions_provider = Some(Box::new(provider));
+        self
+    }
+
+    /// Advertises tools, resources and prompts only if at least one of each
+    /// is registered when the client initializes
+    pub fn with_capabilities_from_registry(mut self) -> Self {
+        self.capabilities_from_registry = true;
+        self
+    }
+
+    /// Returns the capabilities to advertise to a client initializing now
+    async fn advertised_capabilities(&self) -> ServerCapabilities {
+        let mut capabilities = ServerCapabilities {
+            experimental: self.experimental.clone(),
+            ..self.capabilities.clone()
+        };
+        if self.capabilities_from_registry {
+            capabilities.tools =
+                (!self.tools.items.read().await.is_empty()).then(|| ToolsCapability {
+                    list_changed: Some(true),
+                });
+            capabilities.resources =
+                (!self.resources.items.read().await.is_empty()).then(|| ResourcesCapability {
+                    list_changed: Some(true),
+                    subscribe: Some(true),
+                });
+            capabilities.prompts =
+                (!self.prompts.items.read().await.is_empty()).then(|| PromptsCapability {
+                    list_changed: Some(true),
+                });
         }
+        capabilities
+    }
+
+    /// Advertises an experimental, non-standard capability to the client
+    pub fn with_experimental_capability(
+        mut self,
+        key: &str,
+        value: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.experimental.insert(key.into(), value);
+        self
+    }
+
+    /// Returns an experimental capability advertised by the client, once it
+    /// has initialized
+    pub async fn experimental_capability(&self, key: &str) -> Option<serde_json::Value> {
+        self.client
+            .read()
+            .await
+            .as_ref()?
+            .capabilities
+            .experimental
+            .get(key)
+            .cloned()
+            .map(serde_json::Value::Object)
+    }
+
+    /// Renders request counts and latencies in the Prometheus text format,
+    /// for scraping
+    #[cfg(feature = "metrics")]
+    pub fn metrics_text(&self) -> String {
+        self.metrics.text()
+    }
+
+    /// Subscribes to connection lifecycle events
+    pub fn events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Emits a lifecycle event, ignoring the case where nobody is listening
+    fn emit(&self, event: ConnectionEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Subscribes to changes in the client's resource subscriptions
+    pub fn subscription_events(&self) -> broadcast::Receiver<SubscriptionEvent> {
+        self.subscription_changed.subscribe()
+    }
+
+    /// Returns how many resources the client is subscribed to
+    pub async fn active_subscription_count(&self) -> usize {
+        self.subscriptions.read().await.len()
+    }
+
+    /// Returns the URIs of the resources the client is subscribed to, sorted
+    pub async fn subscribed_uris(&self) -> Vec<String> {
+        let mut uris = self
+            .subscriptions
+            .read()
+            .await
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        uris.sort();
+        uris
     }
     fn tool_add(name: &str, tool: impl Tool) {}
     fn tool_remove(name: &str) {}
     fn resource_add(name: &str, resource: impl Resource) {}
     fn resource_remove(name: &str) {}
 
-    pub async fn run(mut self) {
+    /// Serializes the negotiated client state and resource subscriptions so
+    /// they can be restored after a restart. Registered tools and resources
+    /// are not included, since they live in code.
+    pub async fn serialize_state(&self) -> Result<Vec<u8>, McpError> {
+        let client = self
+            .client
+            .read()
+            .await
+            .as_ref()
+            .map(|client| {
+                Ok::<_, McpError>(SavedClientState {
+                    name: client.info.name.clone(),
+                    version: client.info.version.clone(),
+                    protocol_version: client.protocol_version.clone(),
+                    capabilities: serde_json::to_string(&client.capabilities)?,
+                    initialized: client.initialized,
+                })
+            })
+            .transpose()?;
+        let subscriptions = self.subscriptions.read().await.iter().cloned().collect();
+        Ok(postcard::to_allocvec(&SavedState {
+            client,
+            subscriptions,
+        })?)
+    }
+
+    /// Restores state previously written by [`Self::serialize_state`]
+    pub fn restore_state(&mut self, bytes: &[u8]) -> Result<(), McpError> {
+        let state: SavedState = postcard::from_bytes(bytes)?;
+        let client = state
+            .client
+            .map(|client| {
+                Ok::<_, McpError>(ClientState {
+                    info: original::Implementation {
+                        name: client.name,
+                        version: client.version,
+                    },
+                    capabilities: serde_json::from_str(&client.capabilities)?,
+                    protocol_version: client.protocol_version,
+                    initialized: client.initialized,
+                })
+            })
+            .transpose()?;
+        // Watch the restored subscriptions as if the client had just made
+        // them, leaving none behind if one can't be watched
+        #[cfg(feature = "watch")]
+        if let Some(manager) = &self.subscription_manager {
+            for (i, uri) in state.subscriptions.iter().enumerate() {
+                if let Err(err) = manager.subscribe(self, uri) {
+                    for uri in &state.subscriptions[..i] {
+                        manager.unsubscribe(self, uri);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        *self.client.get_mut() = client;
+        *self.subscriptions.get_mut() = state.subscriptions.into_iter().collect();
+        Ok(())
+    }
+
+    /// Forgets the connected client, its subscriptions and log level, and
+    /// abandons requests awaiting its response, so a new client can connect.
+    /// Called when a client disconnects.
+    pub async fn reset_client_state(&self) {
+        *self.client.write().await = None;
+        let subscriptions = std::mem::take(&mut *self.subscriptions.write().await);
+        #[cfg(feature = "watch")]
+        if let Some(manager) = &self.subscription_manager {
+            for uri in &subscriptions {
+                manager.unsubscribe(self, uri);
+            }
+        }
+        for uri in subscriptions {
+            let _ = self
+                .subscription_changed
+                .send(SubscriptionEvent::Unsubscribed { uri });
+        }
+        *self.log_level.write().await = None;
+        // Dropping the senders tells the callers the client is gone
+        self.pending.lock().await.clear();
+    }
+
+    /// Adds resource templates to the server
+    pub fn with_resource_templates(
+        mut self,
+        resource_templates: HashMap<String, Box<dyn ResourceTemplate>>,
+    ) -> Self {
+        self.resource_templates.get_mut().extend(resource_templates);
+        self
+    }
+
+    /// Adds a tool whose calls are handled by a closure, given its JSON
+    /// Schema. Fails if the schema does not describe an object.
+    pub async fn add_tool_dynamic<F, Fut>(
+        &self,
+        name: &str,
+        description: &str,
+        schema: serde_json::Value,
+        handler: F,
+    ) -> Result<(), McpError>
+    where
+        F: Fn(serde_json::Map<String, serde_json::Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ToolCallResult> + Send,
+    {
+        let tool = ClosureTool {
+            description: description.into(),
+            input_schema: serde_json::from_value(schema)?,
+            handler,
+        };
+        self.tools.insert(name, Box::new(tool)).await;
+        Ok(())
+    }
+
+    /// Adds a resource whose contents are read by calling a closure
+    pub async fn add_resource_dynamic<F, Fut>(
+        &self,
+        uri: &str,
+        name: &str,
+        mime_type: Option<&str>,
+        handler: F,
+    ) where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut:
+            Future<Output = Result<Vec<original::ReadResourceResultContentsItem>, McpError>> + Send,
+    {
+        let resource = ClosureResource {
+            uri: uri.into(),
+            name: name.into(),
+            mime_type: mime_type.map(String::from),
+            handler,
+        };
+        self.resources.insert(uri, Box::new(resource)).await;
+    }
+
+    /// Adds tools keyed by name, waiting for any server sharing the registry
+    /// to finish with it
+    pub async fn extend_tools<I>(&self, tools: I)
+    where
+        I: IntoIterator<Item = (String, Box<dyn Tool>)>,
+    {
+        self.tools.items.write().await.extend(tools)
+    }
+
+    /// Adds resources keyed by URI, waiting for any server sharing the
+    /// registry to finish with it
+    pub async fn extend_resources<I>(&self, resources: I)
+    where
+        I: IntoIterator<Item = (String, Box<dyn Resource>)>,
+    {
+        self.resources.items.write().await.extend(resources)
+    }
+
+    /// Adds prompts keyed by name, waiting for any server sharing the
+    /// registry to finish with it
+    pub async fn extend_prompts<I>(&self, prompts: I)
+    where
+        I: IntoIterator<Item = (String, Box<dyn Prompt>)>,
+    {
+        self.prompts.items.write().await.extend(prompts)
+    }
+
+    /// Tells the server a resource has changed, dropping any cached contents
+    /// and notifying the client if it is subscribed
+    pub async fn notify_resource_updated(&self, uri: &str) -> Result<(), McpError> {
+        if let Some(resource) = self.resources.items.read().await.get(uri) {
+            resource.invalidate();
+        }
+        if !self.subscriptions.read().await.contains(uri) {
+            return Ok(());
+        }
+        self.notify(ServerNotification::ResourceUpdated(
+            original::ResourceUpdatedNotificationParams { uri: uri.into() },
+        ))
+    }
+
+    /// Returns the queue of messages to send to the client
+    #[cfg(feature = "watch")]
+    pub(crate) fn outgoing(&self) -> &mpsc::UnboundedSender<Vec<u8>> {
+        &self.outgoing
+    }
+
+    /// Prints a message to stderr if debug echo is on
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn echo(&self, prefix: &str, bytes: &[u8]) {
+        #[cfg(debug_assertions)]
+        if self.debug_echo {
+            eprintln!("{prefix}\n{}", pretty_json(bytes));
+        }
+    }
+
+    /// Queues a notification to the client
+    fn notify(&self, notification: ServerNotification) -> Result<(), McpError> {
+        let msg: OutgoingMessage = crate::schema::Message::Notification {
+            jsonrpc: "2.0".into(),
+            notification,
+        };
+        self.outgoing
+            .send(serde_json::to_vec(&msg)?)
+            .map_err(|_| McpError::Disconnected)
+    }
+
+    /// Returns the URIs of resources, and URI templates of resource
+    /// templates, that start with `partial`, in sorted order
+    pub async fn uri_completions(&self, partial: &str) -> Vec<String> {
+        let mut uris = self
+            .resources
+            .items
+            .read()
+            .await
+            .keys()
+            .filter(|uri| uri.starts_with(partial))
+            .cloned()
+            .collect::<Vec<_>>();
+        uris.extend(
+            self.resource_templates
+                .read()
+                .await
+                .values()
+                .map(|template| template.uri_template())
+                .filter(|uri_template| uri_template.starts_with(partial))
+                .map(String::from),
+        );
+        uris.sort();
+        uris
+    }
+
+    /// Returns the named tool's suggestions for one of its arguments, or
+    /// nothing if there is no such tool
+    pub async fn tool_completions(&self, name: &str, argument: &str, partial: &str) -> Vec<String> {
+        match self.tools.items.read().await.get(name) {
+            Some(tool) => tool.complete(argument, partial).await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the minimum level of log messages the client wants, if it has
+    /// set one
+    pub async fn log_level(&self) -> Option<LoggingLevel> {
+        *self.log_level.read().await
+    }
+
+    /// Stores the level of log messages the client wants, and applies it to
+    /// the `log` crate too if asked to
+    async fn set_log_level(&self, level: LoggingLevel) {
+        *self.log_level.write().await = Some(level);
+        if self.sync_log_level {
+            log::set_max_level(LogLevelAdapter::from(level).log_level_filter());
+        }
+    }
+
+    /// Returns a snapshot of the server's state, for health checks
+    pub async fn health(&self) -> ServerHealth {
+        ServerHealth {
+            uptime: self.start_time.elapsed(),
+            requests_handled: self.requests_handled.load(Ordering::Relaxed),
+            tools_registered: self.tools.items.read().await.len(),
+            resources_registered: self.resources.items.read().await.len(),
+            prompts_registered: self.prompts.items.read().await.len(),
+            client_connected: self.client.read().await.is_some(),
+            last_request_at: *self.last_request_at.read().await,
+        }
+    }
+
+    /// Dumps the server's current state as JSON, for debugging
+    pub async fn inspect(&self) -> serde_json::Value {
+        let mut tools = self
+            .tools
+            .items
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        tools.sort();
+        let mut prompts = self
+            .prompts
+            .items
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        prompts.sort();
+        let mut resources = self
+            .resources
+            .items
+            .read()
+            .await
+            .values()
+            .map(|resource| resource.uri().to_string())
+            .collect::<Vec<_>>();
+        resources.sort();
+        let subscriptions = self.subscribed_uris().await;
+        let client_state = self.client.read().await.as_ref().map(|client| {
+            serde_json::json!({
+                "clientInfo": client.info,
+                "capabilities": client.capabilities,
+                "protocolVersion": client.protocol_version,
+                "initialized": client.initialized,
+            })
+        });
+        serde_json::json!({
+            "tools": tools,
+            "resources": resources,
+            "prompts": prompts,
+            "subscriptions": subscriptions,
+            "active_subscription_count": subscriptions.len(),
+            "client_state": client_state,
+            "log_level": *self.log_level.read().await,
+        })
+    }
+
+    /// Returns whether the client advertised the given capability
+    pub async fn has_capability(&self, cap: ClientCapabilityFlag) -> bool {
+        let client = self.client.read().await;
+        let Some(client) = client.as_ref() else {
+            return false;
+        };
+        match cap {
+            ClientCapabilityFlag::Sampling => client.capabilities.sampling.is_some(),
+            ClientCapabilityFlag::Roots => client.capabilities.roots.is_some(),
+            ClientCapabilityFlag::Experimental => !client.capabilities.experimental.is_empty(),
+        }
+    }
+
+    /// Asks the client to sample from an LLM
+    pub async fn request_sampling(
+        &self,
+        params: original::CreateMessageRequestParams,
+    ) -> Result<original::CreateMessageResult, McpError> {
+        if !self.has_capability(ClientCapabilityFlag::Sampling).await {
+            return Err(McpError::CapabilityNotAvailable("sampling"));
+        }
+        self.request(ServerRequest::CreateMessage(params)).await
+    }
+
+    /// Asks the client for its list of roots
+    pub async fn request_roots(&self) -> Result<original::ListRootsResult, McpError> {
+        if !self.has_capability(ClientCapabilityFlag::Roots).await {
+            return Err(McpError::CapabilityNotAvailable("roots"));
+        }
+        self.request(ServerRequest::ListRoots(Default::default()))
+            .await
+    }
+
+    /// Checks that the client is still responding, returning how long it
+    /// took to answer
+    pub async fn ping(&self) -> Result<Duration, McpError> {
+        let sent_at = Instant::now();
+        let _: original::ResultData = self
+            .request_within(
+                ServerRequest::Ping(Default::default()),
+                Some(self.ping_timeout),
+            )
+            .await?;
+        Ok(sent_at.elapsed())
+    }
+
+    /// Sends a request to the client and waits for its result
+    async fn request<R: DeserializeOwned>(&self, request: ServerRequest) -> Result<R, McpError> {
+        self.request_within(request, None).await
+    }
+
+    /// Sends a request to the client and waits for its result, giving up
+    /// after the timeout if there is one
+    async fn request_within<R: DeserializeOwned>(
+        &self,
+        request: ServerRequest,
+        timeout: Option<Duration>,
+    ) -> Result<R, McpError> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+        let msg: OutgoingMessage = crate::schema::Message::Request {
+            jsonrpc: "2.0".into(),
+            id: RequestId::from(id),
+            request,
+        };
+        self.outgoing
+            .send(serde_json::to_vec(&msg)?)
+            .map_err(|_| McpError::Disconnected)?;
+        let response = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(response) => response,
+                Err(_) => {
+                    self.pending.lock().await.remove(&id);
+                    return Err(McpError::Timeout);
+                }
+            },
+            None => rx.await,
+        };
+        let result = response.map_err(|_| McpError::Disconnected)??;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Hands the outcome of a server-initiated request to whoever is waiting
+    /// on it
+    async fn complete_request(&self, id: &RequestId, result: Result<serde_json::Value, McpError>) {
+        let RequestId::Integer(id) = id else {
+            tracing::warn!(?id, "response to a request the server did not send");
+            return;
+        };
+        match self.pending.lock().await.remove(id) {
+            Some(tx) => {
+                let _ = tx.send(result);
+            }
+            None => tracing::warn!(id, "response to a request the server did not send"),
+        }
+    }
+
+    pub async fn run(&self) {
+        self.serve(None).await
+    }
+
+    /// Reads and handles messages until the client disconnects. Requests are
+    /// handed to `dispatch` along with the time they arrived, if given, and
+    /// answered inline otherwise.
+    async fn serve(&self, dispatch: Option<&Dispatch<'_>>) {
+        let mut transport = self.transport.lock().await;
+        let mut outgoing = self.outgoing_rx.lock().await;
+        let mut broadcasts = self.coordinator.as_ref().map(|c| c.subscribe());
+        self.emit(ConnectionEvent::ClientConnected);
+        // A restored server has already been initialized
+        let mut awaiting_handshake = self.client.read().await.is_none();
+        let handshake = tokio::time::sleep(self.handshake_timeout);
+        tokio::pin!(handshake);
         loop {
-            // Receive a message from the client
-            let msg = self.transport.recv().await.unwrap();
+            // Receive a message from the client, sending any server-initiated
+            // messages while waiting
+            let buf = tokio::select! {
+                buf = transport.recv() => buf.unwrap_or_else(|err| {
+                    // A transport that can no longer be read from has lost
+                    // its client
+                    tracing::warn!(%err, "failed to receive message");
+                    Vec::new()
+                }),
+                _ = &mut handshake, if awaiting_handshake => {
+                    tracing::warn!(timeout = ?self.handshake_timeout, "client never sent initialize");
+                    break;
+                }
+                Some(msg) = outgoing.recv() => {
+                    self.transmit(&mut *transport, &msg).await;
+                    continue;
+                }
+                msg = next_broadcast(&mut broadcasts) => {
+                    self.transmit(&mut *transport, &msg).await;
+                    continue;
+                }
+            };
+            // An empty read means the client closed the transport. Forget
+            // it, so a client reconnecting to the same server starts afresh.
+            if buf.is_empty() {
+                self.emit(ConnectionEvent::ClientDisconnected);
+                self.reset_client_state().await;
+                break;
+            }
+            let received_at = Instant::now();
+            if self.debug_mode {
+                tracing::debug!("received message:\n{}", pretty_json(&buf));
+            }
+            self.echo("← RECV", &buf);
+            // Responses only resolve a pending request with their raw result,
+            // so skip trying them against every possible result type
+            if let Ok(MessageKind {
+                method: None,
+                id: Some(id),
+                result: Some(result),
+            }) = serde_json::from_slice(&buf)
+            {
+                let result = serde_json::from_str(result.get()).map_err(McpError::from);
+                self.complete_request(&id, result).await;
+                continue;
+            }
             // Parse it
-            let msg: ClientMessage = serde_json::from_slice(&msg).unwrap();
+            let msg: ClientMessage = match serde_json::from_slice(&buf) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    tracing::warn!(%err, "failed to parse message");
+                    if let Some(response) = unparsable_response(&buf) {
+                        self.transmit(&mut *transport, &response).await;
+                    }
+                    continue;
+                }
+            };
+            // Check it against deployment policy
+            if let Some(err) = self
+                .validators
+                .iter()
+                .find_map(|validator| validator.validate(&msg).err())
+            {
+                tracing::warn!(%err, "message rejected");
+                // Only requests can be answered
+                if let Message::Request { id, .. } = msg {
+                    let response = error_response(id, err.code, &err.message, None);
+                    let response = serde_json::to_vec(&response).unwrap();
+                    self.transmit(&mut *transport, &response).await;
+                }
+                continue;
+            }
             // Handle it
             use Message::*;
-            dbg!(&msg);
             match msg {
                 Request {
                     jsonrpc,
                     id,
                     request,
                 } => {
-                    use ClientRequest::*;
-                    let response: ServerMessage = match request {
-                        Initialize(InitializeRequestParams {
-                            capabilities,
-                            client_info,
-                            protocol_version,
-                        }) => respond_to(
-                            jsonrpc,
-                            id,
-                            ServerResult::Initialize(InitializeResult {
-                                capabilities: ServerCapabilities {
-                                    experimental: Default::default(),
-                                    logging: Default::default(),
-                                    prompts: Some(ServerCapabilitiesPrompts {
-                                        list_changed: Some(true),
-                                    }),
-                                    resources: Some(ServerCapabilitiesResources {
-                                        list_changed: Some(true),
-                                        subscribe: Some(true),
-                                    }),
-                                    tools: Some(ServerCapabilitiesTools {
-                                        list_changed: Some(true),
-                                    }),
-                                },
-                                instructions: match self.instructions {
-                                    Some(ref instructions) => Some(instructions),
-                                    None => None,
-                                },
-                                meta: Default::default(),
-                                protocol_version,
-                                server_info: Implementation {
-                                    name: self.name.as_str(),
-                                    version: self.version.as_str(),
-                                },
-                            }),
-                        ),
-                        Ping(_) => respond_to(
-                            jsonrpc,
-                            id,
-                            ServerResult::Empty(ResultData {
-                                meta: Default::default(),
-                            }),
-                        ),
-                        ListResources(_) => respond_to(
-                            jsonrpc,
-                            id,
-                            ServerResult::ListResources(ListResourcesResult {
-                                meta: Default::default(),
-                                next_cursor: None,
-                                resources: Vec::new(),
-                            }),
-                        ),
-                        ListResourceTemplates(_) => {
-                            unimplemented!()
-                        }
-                        ReadResource(_) => {
-                            unimplemented!()
-                        }
-                        Subscribe(_) => {
-                            unimplemented!()
-                        }
-                        Unsubscribe(_) => {
-                            unimplemented!()
-                        }
-                        ListPrompts(_) => respond_to(
-                            jsonrpc,
-                            id,
-                            ServerResult::ListPrompts(ListPromptsResult {
-                                meta: Default::default(),
-                                next_cursor: None,
-                                prompts: Vec::new(),
-                            }),
-                        ),
-                        GetPrompt(_) => {
-                            unimplemented!()
-                        }
-                        ListTools(_) => respond_to(
-                            jsonrpc,
-                            id,
-                            ServerResult::ListTools(ListToolsResult {
-                                meta: Default::default(),
-                                next_cursor: None,
-                                tools: Vec::new(),
-                            }),
-                        ),
-                        CallTool(_) => {
-                            unimplemented!()
-                        }
-                        SetLevel(_) => {
-                            unimplemented!()
+                    if let ClientRequest::Initialize(_) = request {
+                        awaiting_handshake = false;
+                    }
+                    match dispatch {
+                        Some(dispatch) => {
+                            // The request borrows from the buffer, so the
+                            // handler parses it again from its own copy
+                            drop(request);
+                            dispatch(buf, received_at).await
                         }
-                        Complete(_) => {
-                            unimplemented!()
+                        None => {
+                            let response = self.answer(jsonrpc, id, request, received_at).await;
+                            self.transmit(&mut *transport, &response).await;
                         }
-                    };
-                    let serialized = serde_json::to_vec(&response).unwrap();
-                    dbg!(String::from_utf8_lossy(&serialized));
-                    self.transport.send(&serialized).await.unwrap();
+                    }
+                }
+                Response { id, .. } => {
+                    // The typed result can't tell the possible results apart,
+                    // so hand the raw JSON to the caller
+                    let result = serde_json::from_slice::<RawResult>(&buf)
+                        .map(|raw| raw.result)
+                        .map_err(McpError::from);
+                    self.complete_request(&id, result).await;
                 }
-                Response { .. } => {}
                 Notification {
                     jsonrpc,
                     notification,
-                } => {}
-                Error(_) => {}
+                } => {
+                    tracing::debug!(method = notification.method(), "received notification");
+                    self.emit(ConnectionEvent::NotificationReceived {
+                        method: notification.method(),
+                    });
+                    match notification {
+                        ClientNotification::Initialized(_) => {
+                            if let Some(client) = self.client.write().await.as_mut() {
+                                client.initialized = true;
+                            }
+                        }
+                        ClientNotification::SetLevel(SetLevelRequestParams { level }) => {
+                            self.set_log_level(level).await;
+                        }
+                        _ => {}
+                    }
+                }
+                Error(err) => {
+                    let result = Err(McpError::Rpc {
+                        code: err.error.code,
+                        message: err.error.message.into(),
+                    });
+                    self.complete_request(&err.id, result).await;
+                }
             }
         }
     }
-}
-pub fn respond_to<'a>(
-    jsonrpc: &'a str,
-    id: RequestId,
-    result: ServerResult<'a>,
-) -> ServerMessage<'a> {
-    Message::Response {
-        jsonrpc,
-        id,
-        result,
-    }
-}
 
-impl MCPServer<StdioTransport> {
-    pub fn new_stdio(
-        name: &str,
-        version: &str,
-        instructions: Option<&str>,
-        tools: HashMap<String, Box<dyn Tool>>,
-        resources: HashMap<String, Box<dyn Resource>>,
-    ) -> Self {
-        Self::new(
-            StdioTransport::new(),
-            name,
-            version,
-            instructions,
-            tools,
-            resources,
-        )
+    /// Sends a message to the client, logging rather than failing if the
+    /// transport can't deliver it, such as when the client has gone
+    async fn transmit(&self, transport: &mut T, msg: &[u8]) {
+        if self.debug_mode {
+            tracing::debug!("sending message:\n{}", pretty_json(msg));
+        }
+        self.echo("→ SEND", msg);
+        if let Err(err) = transport.send(msg).await {
+            tracing::warn!(%err, "failed to send message");
+        }
     }
-}
 
-#[async_trait]
-pub trait Transport {
-    /// Receives and stores a message from the transport
-    async fn recv(&mut self) -> Result<Vec<u8>, io::Error>;
-    /// Sends a messsage on the transport as bytes
-    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error>;
-}
+    /// Handles a request, producing the serialized response to send
+    async fn answer(
+        &self,
+        jsonrpc: &str,
+        id: RequestId,
+        request: ClientRequest<'_>,
+        received_at: Instant,
+    ) -> Vec<u8> {
+        let method = request.method();
+        tracing::debug!(method, ?id, "received request");
+        self.emit(ConnectionEvent::RequestReceived {
+            method,
+            id: id.clone(),
+        });
+        #[cfg(feature = "metrics")]
+        self.metrics.request_received(method);
+        *self.last_request_at.write().await = Some(received_at);
+        let response_id = id.clone();
+        let response = if let Err(err) = self.hooks.run_before(method, &request) {
+            hook_error_response(response_id.clone(), err)
+        } else {
+            let handler = self.handle_request(jsonrpc, id, request);
+            match self.request_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, handler).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        tracing::warn!(method, id = ?response_id, "request timed out");
+                        if let Some(on_timeout) = &self.on_timeout {
+                            on_timeout(response_id.clone(), method);
+                        }
+                        error_response(response_id.clone(), SERVER_ERROR, "Request timed out", None)
+                    }
+                },
+                None => handler.await,
+            }
+        };
+        let response = match &response {
+            crate::schema::Message::Response { result, .. } => {
+                match self.hooks.run_after(method, result) {
+                    Ok(()) => response,
+                    Err(err) => hook_error_response(response_id.clone(), err),
+                }
+            }
+            _ => response,
+        };
+        let response = self.validation.check(response, &response_id);
+        let serialized = serde_json::to_vec(&response).unwrap();
+        tracing::debug!(id = ?response_id, "answered request");
+        #[cfg(feature = "metrics")]
+        match &response {
+            crate::schema::Message::Error(err) => self.metrics.error_sent(err.error.code),
+            _ => self.metrics.response_sent(method),
+        }
+        self.requests_handled.fetch_add(1, Ordering::Relaxed);
+        self.emit(ConnectionEvent::ResponseSent {
+            id: response_id,
+            duration: received_at.elapsed(),
+        });
+        serialized
+    }
 
-/// MCP transport using stdio
-pub struct StdioTransport {
-    stdin: BufReader<Stdin>,
-    stdout: Stdout,
+    /// Handles a single request from the client, producing the message to
+    /// send in response
+    async fn handle_request(
+        &self,
+        jsonrpc: &str,
+        id: RequestId,
+        request: ClientRequest<'_>,
+    ) -> OutgoingMessage {
+        if let Some(handler) = self.router.find(&request) {
+            return match handler.handle(&request).await {
+                Ok(result) => respond_to(jsonrpc, id, result),
+                Err(err) => hook_error_response(id, err),
+            };
+        }
+        use ClientRequest::*;
+        match request {
+            Initialize(InitializeRequestParams {
+                capabilities,
+                client_info,
+                protocol_version,
+            }) => {
+                let info = original::Implementation {
+                    name: client_info.name.into(),
+                    version: client_info.version.into(),
+                };
+                self.emit(ConnectionEvent::ClientInitialized {
+                    client_info: info.clone(),
+                });
+                let instructions = match &self.instructions_provider {
+                    Some(provider) => provider.instructions(&info).await,
+                    None => self.instructions.clone(),
+                };
+                *self.client.write().await = Some(ClientState {
+                    info,
+                    capabilities,
+                    protocol_version: protocol_version.into(),
+                    initialized: false,
+                });
+                respond_to(
+                    jsonrpc,
+                    id,
+                    ServerResult::Initialize(original::InitializeResult {
+                        capabilities: self.advertised_capabilities().await,
+                        instructions,
+                        meta: Default::default(),
+                        protocol_version: protocol_version.into(),
+                        server_info: original::Implementation {
+                            name: self.name.clone(),
+                            version: self.version.clone(),
+                        },
+                    }),
+                )
+            }
+            Ping(_) => respond_to(
+                jsonrpc,
+                id,
+                ServerResult::Empty(original::ResultData {
+                    meta: Default::default(),
+                }),
+            ),
+            ListResources(params) => {
+                let mut resources = self
+                    .resources
+                    .items
+                    .read()
+                    .await
+                    .values()
+                    .map(|resource| original::Resource {
+                        annotations: None,
+                        description: resource.description().map(String::from),
+                        mime_type: resource.mime_type().map(String::from),
+                        name: resource.name().into(),
+                        uri: resource.uri().into(),
+                    })
+                    .collect::<Vec<_>>();
+                resources.sort_by(|a, b| a.uri.cmp(&b.uri));
+                match paginate(resources, params.cursor, self.page_size) {
+                    Some((resources, next_cursor)) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::ListResources(original::ListResourcesResult {
+                            meta: Default::default(),
+                            next_cursor,
+                            resources,
+                        }),
+                    ),
+                    None => error_response(id, INVALID_PARAMS, "Invalid cursor", None),
+                }
+            }
+            ListResourceTemplates(params) => {
+                let mut resource_templates = self
+                    .resource_templates
+                    .read()
+                    .await
+                    .values()
+                    .map(|template| original::ResourceTemplate {
+                        annotations: None,
+                        description: template.description().map(String::from),
+                        mime_type: template.mime_type().map(String::from),
+                        name: template.name().into(),
+                        uri_template: template.uri_template().into(),
+                    })
+                    .collect::<Vec<_>>();
+                resource_templates.sort_by(|a, b| a.uri_template.cmp(&b.uri_template));
+                match paginate(resource_templates, params.cursor, self.page_size) {
+                    Some((resource_templates, next_cursor)) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::ListResourceTemplates(
+                            original::ListResourceTemplatesResult {
+                                meta: Default::default(),
+                                next_cursor,
+                                resource_templates,
+                            },
+                        ),
+                    ),
+                    None => error_response(id, INVALID_PARAMS, "Invalid cursor", None),
+                }
+            }
+            ReadResource(ReadResourceRequestParams { uri }) => {
+                let resources = self.resources.items.read().await;
+                #[cfg(feature = "metrics")]
+                let read_at = Instant::now();
+                // Static resources take priority over templates
+                let contents = match resources.get(uri) {
+                    Some(resource) => resource.read().await,
+                    None => {
+                        let templates = self.resource_templates.read().await;
+                        let Some((template, variables)) = templates.values().find_map(|template| {
+                            match_uri_template(template.uri_template(), uri)
+                                .map(|variables| (template, variables))
+                        }) else {
+                            return error_response(
+                                id,
+                                RESOURCE_NOT_FOUND,
+                                &format!("Resource not found: {uri}"),
+                                Some(McpErrorData::ResourceNotFound { uri: uri.into() }),
+                            );
+                        };
+                        template.read(variables).await
+                    }
+                };
+                #[cfg(feature = "metrics")]
+                self.metrics.observe_resource_read(uri, read_at.elapsed());
+                match contents {
+                    Ok(contents) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::ReadResource(original::ReadResourceResult {
+                            contents,
+                            meta: Default::default(),
+                        }),
+                    ),
+                    // Pass errors from the client through, anything else is
+                    // the server's fault
+                    Err(McpError::Rpc { code, message }) => {
+                        error_response(id, code, &message, None)
+                    }
+                    Err(err) => {
+                        tracing::error!(uri, %err, "failed to read resource");
+                        error_response(
+                            id,
+                            INTERNAL_ERROR,
+                            "Internal error",
+                            Some(McpErrorData::InternalError {
+                                details: err.to_string(),
+                            }),
+                        )
+                    }
+                }
+            }
+            Subscribe(SubscribeRequestParams { uri }) => {
+                #[cfg(feature = "watch")]
+                if let Some(manager) = &self.subscription_manager {
+                    if let Err(err) = manager.subscribe(self, uri) {
+                        return error_response(
+                            id,
+                            INTERNAL_ERROR,
+                            "Failed to watch resource",
+                            Some(McpErrorData::InternalError {
+                                details: err.to_string(),
+                            }),
+                        );
+                    }
+                }
+                if self.subscriptions.write().await.insert(uri.into()) {
+                    let _ = self
+                        .subscription_changed
+                        .send(SubscriptionEvent::Subscribed { uri: uri.into() });
+                }
+                respond_to(
+                    jsonrpc,
+                    id,
+                    ServerResult::Empty(original::ResultData {
+                        meta: Default::default(),
+                    }),
+                )
+            }
+            Unsubscribe(UnsubscribeRequestParams { uri }) => {
+                #[cfg(feature = "watch")]
+                if let Some(manager) = &self.subscription_manager {
+                    manager.unsubscribe(self, uri);
+                }
+                if self.subscriptions.write().await.remove(uri) {
+                    let _ = self
+                        .subscription_changed
+                        .send(SubscriptionEvent::Unsubscribed { uri: uri.into() });
+                }
+                respond_to(
+                    jsonrpc,
+                    id,
+                    ServerResult::Empty(original::ResultData {
+                        meta: Default::default(),
+                    }),
+                )
+            }
+            ListPrompts(params) => {
+                let mut prompts = self
+                    .prompts
+                    .items
+                    .read()
+                    .await
+                    .iter()
+                    .map(|(name, prompt)| original::Prompt {
+                        arguments: prompt.arguments().into_iter().map(Into::into).collect(),
+                        description: prompt.description().map(String::from),
+                        name: name.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                prompts.sort_by(|a, b| a.name.cmp(&b.name));
+                match paginate(prompts, params.cursor, self.page_size) {
+                    Some((prompts, next_cursor)) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::ListPrompts(original::ListPromptsResult {
+                            meta: Default::default(),
+                            next_cursor,
+                            prompts,
+                        }),
+                    ),
+                    None => error_response(id, INVALID_PARAMS, "Invalid cursor", None),
+                }
+            }
+            GetPrompt(GetPromptRequestParams { arguments, name }) => {
+                let prompts = self.prompts.items.read().await;
+                let Some(prompt) = prompts.get(name) else {
+                    return error_response(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Unknown prompt: {name}"),
+                        Some(McpErrorData::PromptNotFound { name: name.into() }),
+                    );
+                };
+                let mut arguments: HashMap<String, String> = arguments
+                    .into_iter()
+                    .map(|(key, value)| (key.into(), value.into()))
+                    .collect();
+                let mut missing = Vec::new();
+                for argument in prompt.arguments() {
+                    if arguments.contains_key(&argument.name) {
+                        continue;
+                    }
+                    match argument.default {
+                        Some(default) => {
+                            arguments.insert(argument.name, default);
+                        }
+                        None if argument.required => missing.push(argument.name),
+                        None => {}
+                    }
+                }
+                if !missing.is_empty() {
+                    return error_response(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Missing required arguments: {}", missing.join(", ")),
+                        None,
+                    );
+                }
+                match prompt.get(arguments).await {
+                    Ok(messages) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::GetPrompt(original::GetPromptResult {
+                            description: prompt.description().map(String::from),
+                            messages,
+                            meta: Default::default(),
+                        }),
+                    ),
+                    Err(McpError::Rpc { code, message }) => {
+                        error_response(id, code, &message, None)
+                    }
+                    Err(err) => error_response(
+                        id,
+                        INTERNAL_ERROR,
+                        "Failed to get prompt",
+                        Some(McpErrorData::InternalError {
+                            details: err.to_string(),
+                        }),
+                    ),
+                }
+            }
+            ListTools(params) => {
+                let mut tools = self
+                    .tools
+                    .items
+                    .read()
+                    .await
+                    .iter()
+                    .map(|(name, tool)| {
+                        let mut input_schema = tool.input_schema();
+                        for (argument, description) in tool.argument_descriptions() {
+                            if let Some(property) = input_schema.properties.get_mut(&argument) {
+                                property.insert("description".into(), description.into());
+                            }
+                        }
+                        original::Tool {
+                            description: tool.description().map(String::from),
+                            input_schema,
+                            name: name.clone(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                tools.sort_by(|a, b| a.name.cmp(&b.name));
+                match paginate(tools, params.cursor, self.page_size) {
+                    Some((tools, next_cursor)) => respond_to(
+                        jsonrpc,
+                        id,
+                        ServerResult::ListTools(original::ListToolsResult {
+                            meta: Default::default(),
+                            next_cursor,
+                            tools,
+                        }),
+                    ),
+                    None => error_response(id, INVALID_PARAMS, "Invalid cursor", None),
+                }
+            }
+            CallTool(CallToolRequestParams { arguments, name }) => {
+                let tools = self.tools.items.read().await;
+                let Some(tool) = tools.get(name) else {
+                    return error_response(
+                        id,
+                        INVALID_PARAMS,
+                        &format!("Unknown tool: {name}"),
+                        Some(McpErrorData::ToolNotFound { name: name.into() }),
+                    );
+                };
+                let called_at = Instant::now();
+                let result: original::CallToolResult = tool.call(arguments.clone()).await.into();
+                let duration = called_at.elapsed();
+                #[cfg(feature = "metrics")]
+                self.metrics.observe_tool_call(name, duration);
+                self.log_call(CallLog {
+                    tool_name: name.into(),
+                    request_id: id.clone(),
+                    duration_ms: duration.as_millis(),
+                    arg_summary: arguments
+                        .iter()
+                        .map(|(key, value)| (key.clone(), json_type(value)))
+                        .collect(),
+                    arguments: self.verbose_logging.then_some(arguments),
+                    result_content_count: result.content.len(),
+                    is_error: result.is_error.unwrap_or(false),
+                });
+                respond_to(jsonrpc, id, ServerResult::CallTool(result))
+            }
+            SetLevel(SetLevelRequestParams { level }) => {
+                self.set_log_level(level).await;
+                respond_to(
+                    jsonrpc,
+                    id,
+                    ServerResult::Empty(original::ResultData {
+                        meta: Default::default(),
+                    }),
+                )
+            }
+            Complete(CompleteRequestParams { argument, ref_ }) => {
+                let values = match ref_ {
+                    CompleteRequestParamsRef::ResourceReference(_) => {
+                        self.uri_completions(argument.value).await
+                    }
+                    CompleteRequestParamsRef::PromptReference(_) => Vec::new(),
+                };
+                respond_to(
+                    jsonrpc,
+                    id,
+                    ServerResult::Complete(completion_result(values)),
+                )
+            }
+        }
+    }
+}
+
+/// Builds the result of a completion request, keeping as many values as the
+/// spec allows
+fn completion_result(mut values: Vec<String>) -> original::CompleteResult {
+    let total = values.len();
+    values.truncate(MAX_COMPLETIONS);
+    original::CompleteResult {
+        completion: original::CompleteResultCompletion {
+            has_more: Some(total > values.len()),
+            total: Some(total as i64),
+            values,
+        },
+        meta: Default::default(),
+    }
+}
+
+/// Returns the page of `items` starting at `cursor`, along with the cursor of
+/// the following page if there is one. Returns `None` if the cursor is
+/// invalid.
+fn paginate<I>(
+    items: Vec<I>,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> Option<(Vec<I>, Option<String>)> {
+    let start = match cursor {
+        Some(cursor) => cursor.parse().ok().filter(|&start| start <= items.len())?,
+        None => 0,
+    };
+    let end = start.saturating_add(page_size).min(items.len());
+    let next_cursor = (end < items.len()).then(|| end.to_string());
+    Some((
+        items.into_iter().skip(start).take(end - start).collect(),
+        next_cursor,
+    ))
+}
+
+/// Returns the error answering a message that isn't a valid client message:
+/// a parse error for invalid JSON, or an invalid request error for a request
+/// whose ID can be read. Other messages can't be answered.
+fn unparsable_response(buf: &[u8]) -> Option<Vec<u8>> {
+    let response = match serde_json::from_slice::<MessageKind>(buf) {
+        Ok(MessageKind { id: Some(id), .. }) => {
+            error_response(id, INVALID_REQUEST, "Invalid request", None)
+        }
+        Ok(_) => return None,
+        // The ID of a message that isn't JSON is unknown, so it is null
+        Err(_) if serde_json::from_slice::<serde::de::IgnoredAny>(buf).is_err() => {
+            let response = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": "Parse error" },
+            });
+            return Some(serde_json::to_vec(&response).expect("JSON always serializes"));
+        }
+        Err(_) => return None,
+    };
+    Some(serde_json::to_vec(&response).expect("error responses always serialize"))
+}
+
+/// Pretty-prints a JSON message for logging, falling back to the raw text if
+/// it does not parse
+fn pretty_json(bytes: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| String::from_utf8_lossy(bytes).into_owned())
+}
+
+pub fn respond_to(jsonrpc: &str, id: RequestId, result: ServerResult) -> OutgoingMessage {
+    crate::schema::Message::Response {
+        jsonrpc: jsonrpc.into(),
+        id,
+        result,
+    }
+}
+
+/// Builds a JSON-RPC error message in response to a request, with optional
+/// structured detail
+pub fn error_response(
+    id: RequestId,
+    code: i64,
+    message: &str,
+    data: Option<McpErrorData>,
+) -> OutgoingMessage {
+    crate::schema::Message::Error(original::JsonrpcError {
+        error: original::JsonrpcErrorError {
+            code,
+            data: data.map(|data| {
+                serde_json::to_value(data).expect("error data always serializes to JSON")
+            }),
+            message: message.into(),
+        },
+        id,
+        jsonrpc: "2.0".into(),
+    })
+}
+
+/// Validates outgoing messages against the schema before they are sent. This
+/// catches bugs in the server implementation rather than in the client.
+pub struct ValidationMiddleware {
+    /// Whether an invalid message panics rather than being replaced with an
+    /// internal error response
+    panic_on_invalid: bool,
+}
+
+impl ValidationMiddleware {
+    /// Constructor. Panics on invalid messages in debug builds.
+    pub fn new() -> Self {
+        Self {
+            panic_on_invalid: cfg!(debug_assertions),
+        }
+    }
+
+    /// Sets whether an invalid message panics rather than being replaced
+    pub fn panic_on_invalid(mut self, panic_on_invalid: bool) -> Self {
+        self.panic_on_invalid = panic_on_invalid;
+        self
+    }
+
+    /// Returns the message if it is valid, or an internal error response to
+    /// send in its place
+    pub fn check(&self, msg: OutgoingMessage, id: &RequestId) -> OutgoingMessage {
+        match msg.validate() {
+            Ok(()) => msg,
+            Err(errors) => {
+                tracing::error!(?id, %errors, "server produced an invalid message");
+                if self.panic_on_invalid {
+                    panic!("server produced an invalid message: {errors}");
+                }
+                error_response(
+                    id.clone(),
+                    INTERNAL_ERROR,
+                    "Internal error",
+                    Some(McpErrorData::ValidationErrors(vec![errors.to_string()])),
+                )
+            }
+        }
+    }
+}
+
+impl Default for ValidationMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A hook run before a request is handled
+type BeforeHook = Box<dyn Fn(&ClientRequest<'_>) -> Result<(), McpError> + Send + Sync>;
+/// A hook run on the result of a request before it is sent
+type AfterHook = Box<dyn Fn(&ServerResult) -> Result<(), McpError> + Send + Sync>;
+
+/// Hooks run before and after requests with particular methods, for things
+/// like audit logging or rate limiting. A hook returning an error sends it to
+/// the client in place of the response.
+#[derive(Default)]
+pub struct HookSet {
+    before: HashMap<String, Vec<BeforeHook>>,
+    after: HashMap<String, Vec<AfterHook>>,
+}
+
+impl HookSet {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook run before each request with the given method is handled.
+    /// If it fails, the request is not handled.
+    pub fn before(
+        mut self,
+        method: &str,
+        hook: impl Fn(&ClientRequest<'_>) -> Result<(), McpError> + Send + Sync + 'static,
+    ) -> Self {
+        self.before
+            .entry(method.into())
+            .or_default()
+            .push(Box::new(hook));
+        self
+    }
+
+    /// Adds a hook run on the result of each request with the given method,
+    /// before it is sent
+    pub fn after(
+        mut self,
+        method: &str,
+        hook: impl Fn(&ServerResult) -> Result<(), McpError> + Send + Sync + 'static,
+    ) -> Self {
+        self.after
+            .entry(method.into())
+            .or_default()
+            .push(Box::new(hook));
+        self
+    }
+
+    /// Runs the hooks for a request, stopping at the first failure
+    fn run_before(&self, method: &str, request: &ClientRequest<'_>) -> Result<(), McpError> {
+        self.before
+            .get(method)
+            .into_iter()
+            .flatten()
+            .try_for_each(|hook| hook(request))
+    }
+
+    /// Runs the hooks for a result, stopping at the first failure
+    fn run_after(&self, method: &str, result: &ServerResult) -> Result<(), McpError> {
+        self.after
+            .get(method)
+            .into_iter()
+            .flatten()
+            .try_for_each(|hook| hook(result))
+    }
+}
+
+/// Converts MCP log levels to those of the `log` and `tracing` crates. Levels
+/// MCP has but they don't are rounded to the nearest more severe level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLevelAdapter(pub LoggingLevel);
+
+impl LogLevelAdapter {
+    /// Returns the matching `log` level
+    pub fn log_level(self) -> log::Level {
+        match self.0 {
+            LoggingLevel::Debug => log::Level::Debug,
+            LoggingLevel::Info => log::Level::Info,
+            LoggingLevel::Notice | LoggingLevel::Warning => log::Level::Warn,
+            LoggingLevel::Error
+            | LoggingLevel::Critical
+            | LoggingLevel::Alert
+            | LoggingLevel::Emergency => log::Level::Error,
+        }
+    }
+
+    /// Returns the `log` filter letting through this level and above
+    pub fn log_level_filter(self) -> log::LevelFilter {
+        self.log_level().to_level_filter()
+    }
+
+    /// Returns the matching `tracing` level. `tracing` can't change its level
+    /// at runtime by itself, so this is for subscribers that can, such as
+    /// through a reload layer.
+    pub fn tracing_level(self) -> tracing::Level {
+        match self.log_level() {
+            log::Level::Error => tracing::Level::ERROR,
+            log::Level::Warn => tracing::Level::WARN,
+            log::Level::Info => tracing::Level::INFO,
+            log::Level::Debug => tracing::Level::DEBUG,
+            log::Level::Trace => tracing::Level::TRACE,
+        }
+    }
+}
+
+impl From<LoggingLevel> for LogLevelAdapter {
+    fn from(level: LoggingLevel) -> Self {
+        Self(level)
+    }
+}
+
+/// Builds the error response for a failed hook, passing JSON-RPC errors
+/// through as they are
+fn hook_error_response(id: RequestId, err: McpError) -> OutgoingMessage {
+    match err {
+        McpError::Rpc { code, message } => error_response(id, code, &message, None),
+        err => error_response(id, SERVER_ERROR, &err.to_string(), None),
+    }
+}
+
+/// Handles requests routed to it by a [`RequestRouter`]
+#[async_trait]
+pub trait RequestHandler: Send + Sync {
+    /// Handles a request, producing its result. JSON-RPC errors are sent to
+    /// the client as they are.
+    async fn handle(&self, request: &ClientRequest<'_>) -> Result<ServerResult, McpError>;
+}
+
+/// Decides whether a route handles a request
+type RoutePredicate = Box<dyn Fn(&ClientRequest<'_>) -> bool + Send + Sync>;
+
+/// Hands each request to the handler of the first route whose predicate
+/// matches, so a server's subsystems can be split into separate handlers.
+/// Requests no route matches are handled by the server as usual.
+#[derive(Default)]
+pub struct RequestRouter {
+    routes: Vec<(RoutePredicate, Box<dyn RequestHandler>)>,
+}
+
+impl RequestRouter {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route, tried after those already added
+    pub fn route(
+        mut self,
+        predicate: impl Fn(&ClientRequest<'_>) -> bool + Send + Sync + 'static,
+        handler: impl RequestHandler + 'static,
+    ) -> Self {
+        self.routes.push((Box::new(predicate), Box::new(handler)));
+        self
+    }
+
+    /// Returns the handler of the first route matching a request
+    fn find(&self, request: &ClientRequest<'_>) -> Option<&dyn RequestHandler> {
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(request))
+            .map(|(_, handler)| handler.as_ref())
+    }
+}
+
+/// A policy incoming messages must pass before they are handled
+pub trait MessageValidator: Send + Sync {
+    /// Returns an error if the message should be rejected
+    fn validate(&self, msg: &ClientMessage<'_>) -> Result<(), ValidationError>;
+}
+
+/// Why a message was rejected, sent to the client as a JSON-RPC error
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Rejects requests and notifications whose method is not listed
+pub struct MethodAllowList {
+    methods: HashSet<String>,
+}
+
+impl MethodAllowList {
+    /// Constructor
+    pub fn new<I, S>(methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            methods: methods.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl MessageValidator for MethodAllowList {
+    fn validate(&self, msg: &ClientMessage<'_>) -> Result<(), ValidationError> {
+        let method = match msg {
+            Message::Request { request, .. } => request.method(),
+            Message::Notification { notification, .. } => notification.method(),
+            _ => return Ok(()),
+        };
+        if self.methods.contains(method) {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Method not allowed: {method}"),
+            })
+        }
+    }
+}
+
+/// Rejects requests and notifications whose params are larger than a number
+/// of bytes, once serialized
+pub struct MaxParamSize {
+    max_bytes: usize,
+}
+
+impl MaxParamSize {
+    /// Constructor
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl MessageValidator for MaxParamSize {
+    fn validate(&self, msg: &ClientMessage<'_>) -> Result<(), ValidationError> {
+        let contents = match msg {
+            Message::Request { request, .. } => serde_json::to_value(request),
+            Message::Notification { notification, .. } => serde_json::to_value(notification),
+            _ => return Ok(()),
+        };
+        let size = contents
+            .ok()
+            .and_then(|contents| Some(contents.get("params")?.to_string().len()))
+            .unwrap_or(0);
+        if size <= self.max_bytes {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                code: INVALID_PARAMS,
+                message: format!(
+                    "Params too large: {size} bytes, limit is {}",
+                    self.max_bytes
+                ),
+            })
+        }
+    }
+}
+
+/// Fans notifications out to every server sharing it, each of which serves
+/// one connection. Each server's run loop subscribes while it runs, and sends
+/// what is broadcast to its client.
+pub struct ServerCoordinator {
+    notifications: broadcast::Sender<Vec<u8>>,
+}
+
+impl Default for ServerCoordinator {
+    fn default() -> Self {
+        Self {
+            notifications: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl ServerCoordinator {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends a notification to every running server's client
+    pub async fn broadcast_notification(
+        &self,
+        notification: ServerNotification,
+    ) -> Result<(), McpError> {
+        let msg: OutgoingMessage = crate::schema::Message::Notification {
+            jsonrpc: "2.0".into(),
+            notification,
+        };
+        // No servers running is not an error, there is just no one to tell
+        let _ = self.notifications.send(serde_json::to_vec(&msg)?);
+        Ok(())
+    }
+
+    /// Returns a receiver for notifications broadcast from now on
+    fn subscribe(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.notifications.subscribe()
+    }
+}
+
+/// Waits until a request handed to [`Dispatch`] can be handled
+type DispatchFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+/// Hands a request, along with the time it arrived, to another task
+type Dispatch<'a> = dyn Fn(Vec<u8>, Instant) -> DispatchFuture + Sync + 'a;
+
+/// Waits for the next broadcast notification, or forever without a
+/// coordinator
+async fn next_broadcast(broadcasts: &mut Option<broadcast::Receiver<Vec<u8>>>) -> Vec<u8> {
+    let Some(broadcasts) = broadcasts else {
+        return std::future::pending().await;
+    };
+    loop {
+        match broadcasts.recv().await {
+            Ok(msg) => return msg,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "dropped broadcast notifications");
+            }
+            // The server holds the coordinator, so it can't be dropped
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+impl<T> MCPServer<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    /// Like [`Self::run`], but answers up to `max_concurrency` requests at
+    /// once, each in its own task. Responses are sent as they complete,
+    /// rather than in the order the requests arrived. Notifications are
+    /// still handled in order. Once the limit is reached, no more messages
+    /// are read until a request finishes.
+    pub async fn run_concurrent(self: &Arc<Self>, max_concurrency: NonZeroUsize) {
+        let permits = Arc::new(Semaphore::new(max_concurrency.get()));
+        let dispatch = |buf: Vec<u8>, received_at: Instant| -> DispatchFuture {
+            let server = Arc::clone(self);
+            let permits = Arc::clone(&permits);
+            Box::pin(async move {
+                // The permit is taken before spawning, so at most
+                // `max_concurrency` tasks exist at once
+                let Ok(permit) = permits.acquire_owned().await else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    let Ok(Message::Request {
+                        jsonrpc,
+                        id,
+                        request,
+                    }) = serde_json::from_slice::<ClientMessage>(&buf)
+                    else {
+                        return;
+                    };
+                    let response = server.answer(jsonrpc, id, request, received_at).await;
+                    // The run loop sends queued messages to the client
+                    if server.outgoing.send(response).is_err() {
+                        tracing::warn!("connection closed before the response was sent");
+                    }
+                });
+            })
+        };
+        self.serve(Some(&dispatch)).await
+    }
+}
+
+impl MCPServer<StdioTransport> {
+    pub fn new_stdio(
+        name: &str,
+        version: &str,
+        instructions: Option<&str>,
+        tools: HashMap<String, Box<dyn Tool>>,
+        resources: HashMap<String, Box<dyn Resource>>,
+    ) -> Self {
+        Self::new(
+            StdioTransport::new(),
+            name,
+            version,
+            instructions,
+            tools,
+            resources,
+        )
+    }
+}
+
+/// Marks an [`MCPServerBuilder`] that has no name yet
+pub struct NoName;
+/// Marks an [`MCPServerBuilder`] that has a name
+pub struct HasName(String);
+/// Marks an [`MCPServerBuilder`] that has no version yet
+pub struct NoVersion;
+/// Marks an [`MCPServerBuilder`] that has a version
+pub struct HasVersion(String);
+
+/// Settings for a server, which can be loaded from a TOML or JSON file
+/// instead of set in code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Name the server reports to clients
+    pub name: String,
+    /// Version the server reports to clients
+    pub version: String,
+    /// Instructions telling clients how to use the server
+    #[serde(default)]
+    pub instructions: Option<String>,
+    /// Number of items returned per page of a list request. Configs setting
+    /// it to zero fail to load.
+    #[serde(default = "ServerConfig::default_page_size")]
+    pub page_size: NonZeroUsize,
+    /// Seconds to wait for the client to send `initialize`
+    #[serde(default = "ServerConfig::default_timeout_secs")]
+    pub handshake_timeout_secs: u64,
+    /// Seconds a request may take to handle, if limited
+    #[serde(default)]
+    pub request_timeout_secs: Option<u64>,
+    /// Seconds to wait for the client to answer a ping
+    #[serde(default = "ServerConfig::default_timeout_secs")]
+    pub ping_timeout_secs: u64,
+    /// Whether to advertise tools
+    #[serde(default = "ServerConfig::enabled")]
+    pub tools: bool,
+    /// Whether to advertise resources and resource subscriptions
+    #[serde(default = "ServerConfig::enabled")]
+    pub resources: bool,
+    /// Whether to advertise prompts
+    #[serde(default = "ServerConfig::enabled")]
+    pub prompts: bool,
+    /// Whether to advertise logging
+    #[serde(default = "ServerConfig::enabled")]
+    pub logging: bool,
+}
+
+impl ServerConfig {
+    /// Constructor, with every other setting at its default
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            instructions: None,
+            page_size: Self::default_page_size(),
+            handshake_timeout_secs: Self::default_timeout_secs(),
+            request_timeout_secs: None,
+            ping_timeout_secs: Self::default_timeout_secs(),
+            tools: true,
+            resources: true,
+            prompts: true,
+            logging: true,
+        }
+    }
+
+    /// Returns the capabilities the flags describe
+    pub fn capabilities(&self) -> ServerCapabilities {
+        let mut capabilities = ServerCapabilitiesBuilder::new();
+        if self.tools {
+            capabilities = capabilities.with_tools(true);
+        }
+        if self.resources {
+            capabilities = capabilities.with_resources(true, true);
+        }
+        if self.prompts {
+            capabilities = capabilities.with_prompts(true);
+        }
+        if self.logging {
+            capabilities = capabilities.with_logging();
+        }
+        capabilities.build()
+    }
+
+    fn default_page_size() -> NonZeroUsize {
+        NonZeroUsize::new(PAGE_SIZE).expect("the default page size is not zero")
+    }
+
+    fn default_timeout_secs() -> u64 {
+        10
+    }
+
+    fn enabled() -> bool {
+        true
+    }
+}
+
+/// Builds an [`MCPServer`], which can only be built once its name and
+/// version are set
+pub struct MCPServerBuilder<N, V> {
+    name: N,
+    version: V,
+    instructions: Option<String>,
+    page_size: Option<NonZeroUsize>,
+    handshake_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    ping_timeout: Option<Duration>,
+    capabilities: Option<ServerCapabilities>,
+    tools: HashMap<String, Box<dyn Tool>>,
+    resources: HashMap<String, Box<dyn Resource>>,
+    prompts: HashMap<String, Box<dyn Prompt>>,
 }
 
-impl StdioTransport {
+impl MCPServerBuilder<NoName, NoVersion> {
     /// Constructor
     pub fn new() -> Self {
         Self {
-            stdin: BufReader::new(tokio::io::stdin()),
-            stdout: tokio::io::stdout(),
+            name: NoName,
+            version: NoVersion,
+            instructions: None,
+            page_size: None,
+            handshake_timeout: None,
+            request_timeout: None,
+            ping_timeout: None,
+            capabilities: None,
+            tools: HashMap::new(),
+            resources: HashMap::new(),
+            prompts: HashMap::new(),
         }
     }
 }
 
+impl Default for MCPServerBuilder<NoName, NoVersion> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, V> MCPServerBuilder<N, V> {
+    /// Sets the name the server reports to clients
+    pub fn with_name(self, name: &str) -> MCPServerBuilder<HasName, V> {
+        MCPServerBuilder {
+            name: HasName(name.into()),
+            version: self.version,
+            instructions: self.instructions,
+            page_size: self.page_size,
+            handshake_timeout: self.handshake_timeout,
+            request_timeout: self.request_timeout,
+            ping_timeout: self.ping_timeout,
+            capabilities: self.capabilities,
+            tools: self.tools,
+            resources: self.resources,
+            prompts: self.prompts,
+        }
+    }
+
+    /// Sets the version the server reports to clients
+    pub fn with_version(self, version: &str) -> MCPServerBuilder<N, HasVersion> {
+        MCPServerBuilder {
+            name: self.name,
+            version: HasVersion(version.into()),
+            instructions: self.instructions,
+            page_size: self.page_size,
+            handshake_timeout: self.handshake_timeout,
+            request_timeout: self.request_timeout,
+            ping_timeout: self.ping_timeout,
+            capabilities: self.capabilities,
+            tools: self.tools,
+            resources: self.resources,
+            prompts: self.prompts,
+        }
+    }
+
+    /// Sets instructions telling clients how to use the server
+    pub fn with_instructions(mut self, instructions: &str) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets how many items are returned per page of a list request
+    pub fn with_page_size(mut self, page_size: NonZeroUsize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Sets how long to wait for the client to send `initialize`
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long a request may take to handle
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long a ping waits for the client to answer
+    pub fn with_ping_timeout(mut self, timeout: Duration) -> Self {
+        self.ping_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the capabilities advertised to the client
+    pub fn with_capabilities(mut self, capabilities: ServerCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Adds a tool
+    pub fn with_tool(mut self, name: &str, tool: impl Tool + 'static) -> Self {
+        self.tools.insert(name.into(), Box::new(tool));
+        self
+    }
+
+    /// Adds a resource, keyed by its URI
+    pub fn with_resource(mut self, resource: impl Resource + 'static) -> Self {
+        self.resources
+            .insert(resource.uri().into(), Box::new(resource));
+        self
+    }
+
+    /// Adds a prompt
+    pub fn with_prompt(mut self, name: &str, prompt: impl Prompt + 'static) -> Self {
+        self.prompts.insert(name.into(), Box::new(prompt));
+        self
+    }
+}
+
+impl MCPServerBuilder<HasName, HasVersion> {
+    /// Creates a builder with the settings from a config file
+    pub fn from_config(config: ServerConfig) -> Self {
+        let capabilities = config.capabilities();
+        let mut builder = MCPServerBuilder::new()
+            .with_name(&config.name)
+            .with_version(&config.version)
+            .with_page_size(config.page_size)
+            .with_handshake_timeout(Duration::from_secs(config.handshake_timeout_secs))
+            .with_ping_timeout(Duration::from_secs(config.ping_timeout_secs))
+            .with_capabilities(capabilities);
+        builder.instructions = config.instructions;
+        builder.request_timeout = config.request_timeout_secs.map(Duration::from_secs);
+        builder
+    }
+
+    /// Builds a server speaking over the given transport
+    pub fn build<T: Transport>(self, transport: T) -> MCPServer<T> {
+        let mut server = MCPServer::new_with_registry(
+            transport,
+            &self.name.0,
+            &self.version.0,
+            self.instructions.as_deref(),
+            Arc::new(ToolRegistry::new(self.tools)),
+            Arc::new(ResourceRegistry::new(self.resources)),
+            Arc::new(PromptRegistry::new(self.prompts)),
+        );
+        if let Some(page_size) = self.page_size {
+            server = server.with_page_size(page_size);
+        }
+        if let Some(timeout) = self.handshake_timeout {
+            server = server.with_handshake_timeout(timeout);
+        }
+        if let Some(timeout) = self.request_timeout {
+            server = server.with_request_timeout(timeout);
+        }
+        if let Some(timeout) = self.ping_timeout {
+            server = server.with_ping_timeout(timeout);
+        }
+        if let Some(capabilities) = self.capabilities {
+            server = server.with_capabilities(capabilities);
+        }
+        server
+    }
+}
+
+/// Named items a server exposes to clients, which can be shared between
+/// servers and changed while they run
+pub struct Registry<V: ?Sized> {
+    items: RwLock<HashMap<String, Box<V>>>,
+}
+
+/// Tools keyed by name
+pub type ToolRegistry = Registry<dyn Tool>;
+/// Resources keyed by URI
+pub type ResourceRegistry = Registry<dyn Resource>;
+/// Prompts keyed by name
+pub type PromptRegistry = Registry<dyn Prompt>;
+
+impl<V: ?Sized> Registry<V> {
+    /// Constructor
+    pub fn new(items: HashMap<String, Box<V>>) -> Self {
+        Self {
+            items: RwLock::new(items),
+        }
+    }
+
+    /// Adds an item, returning the one it replaced
+    pub async fn insert(&self, key: &str, item: Box<V>) -> Option<Box<V>> {
+        self.items.write().await.insert(key.into(), item)
+    }
+
+    /// Removes an item, returning it
+    pub async fn remove(&self, key: &str) -> Option<Box<V>> {
+        self.items.write().await.remove(key)
+    }
+
+    /// Returns every item, keyed by name
+    pub(crate) async fn items(&self) -> RwLockReadGuard<'_, HashMap<String, Box<V>>> {
+        self.items.read().await
+    }
+}
+
+impl<V: ?Sized> Extend<(String, Box<V>)> for Registry<V> {
+    fn extend<I: IntoIterator<Item = (String, Box<V>)>>(&mut self, iter: I) {
+        self.items.get_mut().extend(iter)
+    }
+}
+
+impl<V: ?Sized> Default for Registry<V> {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+/// A tool the server exposes to clients, keyed by its name
 #[async_trait]
-impl Transport for StdioTransport {
-    /// Receives a message from the transport as bytes
-    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
-        // Read a line from stdin
-        let mut buf = Vec::new();
-        self.stdin.read_until(b'\n', &mut buf).await.map(|_| buf)
+pub trait Tool: Send + Sync {
+    /// Description of what the tool does
+    fn description(&self) -> Option<&str> {
+        None
     }
-    /// Sends a messsage on the transport as bytes
-    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
-        self.stdout.write_all(buf).await?;
-        self.stdout.write_u8(b'\n').await?;
-        self.stdout.flush().await
+    /// JSON Schema describing the tool's arguments
+    fn input_schema(&self) -> original::ToolInputSchema;
+    /// Descriptions of the tool's arguments by name, which replace those in
+    /// the input schema when the tool is listed. Arguments missing from the
+    /// schema are ignored.
+    fn argument_descriptions(&self) -> HashMap<String, String> {
+        HashMap::new()
     }
+    /// Calls the tool with the given arguments
+    async fn call(&self, arguments: serde_json::Map<String, serde_json::Value>) -> ToolCallResult;
+    /// Suggests values for the named argument that start with `partial`.
+    /// Tools that don't offer completions suggest nothing.
+    // No schema revision has a ref/tool completion reference yet, so
+    // completion/complete requests can't reach this until one does. It can
+    // still be called through `MCPServer::tool_completions`.
+    async fn complete(&self, _argument: &str, _partial: &str) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A tool backed by a closure, added with [`MCPServer::add_tool_dynamic`]
+struct ClosureTool<F> {
+    description: String,
+    input_schema: original::ToolInputSchema,
+    handler: F,
+}
+
+#[async_trait]
+impl<F, Fut> Tool for ClosureTool<F>
+where
+    F: Fn(serde_json::Map<String, serde_json::Value>) -> Fut + Send + Sync,
+    Fut: Future<Output = ToolCallResult> + Send,
+{
+    fn description(&self) -> Option<&str> {
+        Some(&self.description)
+    }
+
+    fn input_schema(&self) -> original::ToolInputSchema {
+        self.input_schema.clone()
+    }
+
+    async fn call(&self, arguments: serde_json::Map<String, serde_json::Value>) -> ToolCallResult {
+        (self.handler)(arguments).await
+    }
+}
+
+/// A tool backed by a closure that is handed a clone of some shared state,
+/// such as a registry, on every call
+pub struct ContextualTool<S, F> {
+    state: S,
+    description: String,
+    input_schema: original::ToolInputSchema,
+    handler: F,
 }
 
-pub trait Tool {}
-pub trait Resource {}
+impl<S, F, Fut> ContextualTool<S, F>
+where
+    S: Clone + Send + Sync,
+    F: Fn(S, ToolCallArguments) -> Fut + Send + Sync,
+    Fut: Future<Output = ToolCallResult> + Send,
+{
+    /// Constructor, given the tool's JSON Schema. Fails if the schema does
+    /// not describe an object.
+    pub fn new(
+        state: S,
+        description: &str,
+        schema: serde_json::Value,
+        handler: F,
+    ) -> Result<Self, McpError> {
+        Ok(Self {
+            state,
+            description: description.into(),
+            input_schema: serde_json::from_value(schema)?,
+            handler,
+        })
+    }
+}
+
+#[async_trait]
+impl<S, F, Fut> Tool for ContextualTool<S, F>
+where
+    S: Clone + Send + Sync,
+    F: Fn(S, ToolCallArguments) -> Fut + Send + Sync,
+    Fut: Future<Output = ToolCallResult> + Send,
+{
+    fn description(&self) -> Option<&str> {
+        Some(&self.description)
+    }
+
+    fn input_schema(&self) -> original::ToolInputSchema {
+        self.input_schema.clone()
+    }
+
+    async fn call(&self, arguments: serde_json::Map<String, serde_json::Value>) -> ToolCallResult {
+        (self.handler)(self.state.clone(), arguments.into()).await
+    }
+}
+
+/// Record of a single tool call, for operators
+#[derive(Debug, Clone)]
+pub struct CallLog {
+    pub tool_name: String,
+    pub request_id: RequestId,
+    pub duration_ms: u128,
+    /// Name and JSON type of each argument, leaving out values for privacy
+    pub arg_summary: Vec<(String, &'static str)>,
+    /// Argument values, only recorded with verbose logging
+    pub arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    pub result_content_count: usize,
+    pub is_error: bool,
+}
+
+/// Returns the JSON type name of a value
+fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// A single piece of content returned by a tool
+pub type ContentItem = original::CallToolResultContentItem;
+
+/// Creates text content for a tool result
+pub fn text_content(text: impl Into<String>) -> ContentItem {
+    ContentItem::TextContent(original::TextContent {
+        annotations: None,
+        text: text.into(),
+        type_: "text".into(),
+    })
+}
+
+/// Creates a text message for a prompt
+pub fn text_message(role: original::Role, text: impl Into<String>) -> original::PromptMessage {
+    original::PromptMessage {
+        content: original::PromptMessageContent::TextContent(original::TextContent {
+            annotations: None,
+            text: text.into(),
+            type_: "text".into(),
+        }),
+        role,
+    }
+}
+
+/// Creates image content for a tool result, base64-encoding the data
+pub fn image_content(data: &[u8], mime_type: impl Into<String>) -> ContentItem {
+    ContentItem::ImageContent(original::ImageContent {
+        annotations: None,
+        data: BASE64_STANDARD.encode(data),
+        mime_type: mime_type.into(),
+        type_: "image".into(),
+    })
+}
+
+/// The outcome of calling a tool. Errors are reported to the client as tool
+/// output with `isError` set, rather than as JSON-RPC errors, so the model can
+/// see and react to them.
+pub type ToolCallResult = Result<Vec<ContentItem>, ToolCallError>;
+
+/// A tool call that failed, with a message explaining why
+#[derive(Debug, Clone)]
+pub struct ToolCallError {
+    message: String,
+}
+
+impl ToolCallError {
+    /// Constructor
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Human-readable explanation of the failure
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for ToolCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ToolCallError {}
+
+impl From<McpError> for ToolCallError {
+    fn from(err: McpError) -> Self {
+        Self::new(err.to_string())
+    }
+}
+
+impl From<ToolCallResult> for original::CallToolResult {
+    fn from(result: ToolCallResult) -> Self {
+        let (content, is_error) = match result {
+            Ok(content) => (content, None),
+            Err(err) => (vec![text_content(err.message)], Some(true)),
+        };
+        Self {
+            content,
+            is_error,
+            meta: Default::default(),
+        }
+    }
+}
+
+/// Shorthands for building the result of a tool call, to return from
+/// [`Tool::call`]
+pub struct ToolCallResponse;
+
+impl ToolCallResponse {
+    /// A result holding some text
+    pub fn text(msg: &str) -> ToolCallResult {
+        Self::builder().with_text(msg).build()
+    }
+
+    /// A failed result explaining why
+    pub fn error(msg: &str) -> ToolCallResult {
+        Err(ToolCallError::new(msg))
+    }
+
+    /// A result holding a JSON value, as text
+    pub fn json(val: serde_json::Value) -> ToolCallResult {
+        Self::builder().with_json(&val).build()
+    }
+
+    /// Builds a result holding several pieces of content
+    pub fn builder() -> ToolCallResponseBuilder {
+        ToolCallResponseBuilder::default()
+    }
+}
+
+/// Builds the result of a tool call piece by piece
+#[derive(Default)]
+pub struct ToolCallResponseBuilder {
+    content: Vec<ContentItem>,
+}
+
+impl ToolCallResponseBuilder {
+    /// Adds some content
+    pub fn with_content(mut self, content: ContentItem) -> Self {
+        self.content.push(content);
+        self
+    }
+
+    /// Adds some text
+    pub fn with_text(self, text: &str) -> Self {
+        self.with_content(text_content(text))
+    }
+
+    /// Adds a JSON value, as text
+    pub fn with_json(self, val: &serde_json::Value) -> Self {
+        self.with_content(text_content(val.to_string()))
+    }
+
+    /// Adds an image
+    pub fn with_image(self, data: &[u8], mime_type: &str) -> Self {
+        self.with_content(image_content(data, mime_type))
+    }
+
+    /// Returns the successful result holding the content added
+    pub fn build(self) -> ToolCallResult {
+        Ok(self.content)
+    }
+}
+
+/// Arguments of a tool call, with accessors that check each argument is
+/// present and has the expected JSON type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolCallArguments(pub serde_json::Map<String, serde_json::Value>);
+
+impl ToolCallArguments {
+    /// Returns an argument, or an error if it is missing
+    fn get(&self, key: &str) -> Result<&serde_json::Value, McpError> {
+        self.0
+            .get(key)
+            .ok_or_else(|| McpError::MissingArgument(key.into()))
+    }
+
+    /// Returns the error for an argument of the wrong type
+    fn wrong_type(key: &str, expected: &'static str) -> McpError {
+        McpError::WrongArgumentType {
+            name: key.into(),
+            expected,
+        }
+    }
+
+    /// Returns a string argument
+    pub fn get_str(&self, key: &str) -> Result<&str, McpError> {
+        self.get(key)?
+            .as_str()
+            .ok_or_else(|| Self::wrong_type(key, "a string"))
+    }
+
+    /// Returns an integer argument
+    pub fn get_i64(&self, key: &str) -> Result<i64, McpError> {
+        self.get(key)?
+            .as_i64()
+            .ok_or_else(|| Self::wrong_type(key, "an integer"))
+    }
+
+    /// Returns a boolean argument
+    pub fn get_bool(&self, key: &str) -> Result<bool, McpError> {
+        self.get(key)?
+            .as_bool()
+            .ok_or_else(|| Self::wrong_type(key, "a boolean"))
+    }
+
+    /// Returns an array argument
+    pub fn get_array(&self, key: &str) -> Result<&[serde_json::Value], McpError> {
+        self.get(key)?
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or_else(|| Self::wrong_type(key, "an array"))
+    }
+
+    /// Returns an object argument
+    pub fn get_object(
+        &self,
+        key: &str,
+    ) -> Result<&serde_json::Map<String, serde_json::Value>, McpError> {
+        self.get(key)?
+            .as_object()
+            .ok_or_else(|| Self::wrong_type(key, "an object"))
+    }
+}
+
+impl From<serde_json::Map<String, serde_json::Value>> for ToolCallArguments {
+    fn from(arguments: serde_json::Map<String, serde_json::Value>) -> Self {
+        Self(arguments)
+    }
+}
+
+/// A resource the server exposes to clients, keyed by its URI
+#[async_trait]
+pub trait Resource: Send + Sync {
+    /// URI identifying the resource
+    fn uri(&self) -> &str;
+    /// Human-readable name of the resource
+    fn name(&self) -> &str;
+    /// Description of what the resource contains
+    fn description(&self) -> Option<&str> {
+        None
+    }
+    /// MIME type of the resource's contents, if known
+    fn mime_type(&self) -> Option<&str> {
+        None
+    }
+    /// Reads the resource's current contents. Reads may run concurrently, so
+    /// resources that keep state between reads use interior mutability.
+    async fn read(&self) -> Result<Vec<original::ReadResourceResultContentsItem>, McpError>;
+    /// Drops any cached contents, called when the resource is updated
+    fn invalidate(&self) {}
+}
+
+/// A resource backed by a closure, added with
+/// [`MCPServer::add_resource_dynamic`]
+struct ClosureResource<F> {
+    uri: String,
+    name: String,
+    mime_type: Option<String>,
+    handler: F,
+}
+
+#[async_trait]
+impl<F, Fut> Resource for ClosureResource<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Vec<original::ReadResourceResultContentsItem>, McpError>> + Send,
+{
+    fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn mime_type(&self) -> Option<&str> {
+        self.mime_type.as_deref()
+    }
+
+    async fn read(&self) -> Result<Vec<original::ReadResourceResultContentsItem>, McpError> {
+        (self.handler)().await
+    }
+}
+
+/// A prompt template the server offers to clients
+#[async_trait]
+pub trait Prompt: Send + Sync {
+    /// Name identifying the prompt
+    fn name(&self) -> &str;
+    /// Description of what the prompt does
+    fn description(&self) -> Option<&str> {
+        None
+    }
+    /// Arguments the prompt can be filled in with
+    fn arguments(&self) -> Vec<PromptArgument> {
+        Vec::new()
+    }
+    /// Fills in the prompt with the given arguments
+    async fn get(
+        &self,
+        arguments: HashMap<String, String>,
+    ) -> Result<Vec<original::PromptMessage>, McpError>;
+}
+
+/// An argument a [`Prompt`] can be filled in with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PromptArgument {
+    /// Name of the argument
+    pub name: String,
+    /// Description of what the argument is for
+    pub description: Option<String>,
+    /// Whether the client must give the argument, unless it has a default
+    pub required: bool,
+    /// Value used when the client doesn't give the argument
+    pub default: Option<String>,
+}
+
+impl From<PromptArgument> for original::PromptArgument {
+    fn from(argument: PromptArgument) -> Self {
+        Self {
+            description: argument.description,
+            name: argument.name,
+            required: Some(argument.required),
+        }
+    }
+}
+
+/// Computes the instructions sent to a client when it initializes
+#[async_trait]
+pub trait InstructionsProvider: Send + Sync {
+    /// Returns instructions for the given client, if any
+    async fn instructions(&self, client_info: &original::Implementation) -> Option<String>;
+}
+
+/// A family of resources whose URIs follow an RFC 6570 URI template
+#[async_trait]
+pub trait ResourceTemplate: Send + Sync {
+    /// URI template matching the resources
+    fn uri_template(&self) -> &str;
+    /// Human-readable name of the resources
+    fn name(&self) -> &str;
+    /// Description of what the resources contain
+    fn description(&self) -> Option<&str> {
+        None
+    }
+    /// MIME type shared by all matching resources, if any
+    fn mime_type(&self) -> Option<&str> {
+        None
+    }
+    /// Reads the resource whose URI matched the template with the given
+    /// variables. Templates that only describe resources can't be read.
+    async fn read(
+        &self,
+        _variables: HashMap<String, String>,
+    ) -> Result<Vec<original::ReadResourceResultContentsItem>, McpError> {
+        Err(McpError::Rpc {
+            code: RESOURCE_NOT_FOUND,
+            message: format!("Resources of {} can't be read", self.uri_template()),
+        })
+    }
+}
+
+/// Matches a URI against a URI template, returning the value of each
+/// variable. Supports simple `{var}` expressions, which match up to the next
+/// `/`, `?`, `#` or literal, and reserved `{+var}` expressions, which match
+/// up to the next literal.
+fn match_uri_template(template: &str, uri: &str) -> Option<HashMap<String, String>> {
+    let mut variables = HashMap::new();
+    let mut template = template;
+    let mut uri = uri;
+    while let Some(start) = template.find('{') {
+        uri = uri.strip_prefix(&template[..start])?;
+        let end = start + template[start..].find('}')?;
+        let expression = &template[start + 1..end];
+        template = &template[end + 1..];
+        let (name, reserved) = match expression.strip_prefix('+') {
+            Some(name) => (name, true),
+            None => (expression, false),
+        };
+        // The value runs until the literal text that follows it
+        let literal = &template[..template.find('{').unwrap_or(template.len())];
+        let mut len = match literal {
+            "" if template.is_empty() => uri.len(),
+            "" => return None,
+            literal => uri.find(literal)?,
+        };
+        if !reserved {
+            len = len.min(uri.find(['/', '?', '#']).unwrap_or(uri.len()));
+        }
+        variables.insert(name.to_string(), uri[..len].to_string());
+        uri = &uri[len..];
+    }
+    (uri == template).then_some(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{match_uri_template, paginate};
+    use std::collections::HashMap;
+
+    /// Matches a URI, returning its variables as sorted pairs
+    fn variables(template: &str, uri: &str) -> Option<Vec<(String, String)>> {
+        let mut variables: Vec<_> = match_uri_template(template, uri)?.into_iter().collect();
+        variables.sort();
+        Some(variables)
+    }
+
+    /// Builds the expected variables
+    fn expected(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+        Some(
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn uri_template_with_several_variables() {
+        assert_eq!(
+            variables("repo:///{user}/{repo}/blob", "repo:///alice/mcp/blob"),
+            expected(&[("repo", "mcp"), ("user", "alice")])
+        );
+    }
+
+    #[test]
+    fn uri_template_variables_end_at_the_next_literal() {
+        assert_eq!(
+            variables("users/{id}-{rev}.json", "users/42-3.json"),
+            expected(&[("id", "42"), ("rev", "3")])
+        );
+        assert_eq!(variables("users/{id}/posts", "users/1/comments"), None);
+        // Adjacent variables have no literal between them to split on
+        assert_eq!(variables("{a}{b}", "xy"), None);
+    }
+
+    #[test]
+    fn uri_template_variables_can_be_empty() {
+        assert_eq!(
+            variables("users/{id}/posts", "users//posts"),
+            expected(&[("id", "")])
+        );
+        assert_eq!(
+            variables("files/{+path}", "files/"),
+            expected(&[("path", "")])
+        );
+    }
+
+    #[test]
+    fn uri_template_trailing_variable() {
+        assert_eq!(
+            variables("files/{name}", "files/a.txt"),
+            expected(&[("name", "a.txt")])
+        );
+        // Only reserved expansion runs across segments
+        assert_eq!(variables("files/{name}", "files/a/b.txt"), None);
+        assert_eq!(
+            variables("files/{+path}", "files/a/b.txt"),
+            expected(&[("path", "a/b.txt")])
+        );
+    }
+
+    #[test]
+    fn uri_template_without_variables() {
+        assert_eq!(
+            match_uri_template("config://app", "config://app"),
+            Some(HashMap::new())
+        );
+        assert_eq!(match_uri_template("config://app", "config://other"), None);
+    }
+
+    #[test]
+    fn pages_cover_every_item_once() {
+        let items: Vec<u32> = (0..5).collect();
+        assert_eq!(
+            paginate(items.clone(), None, 2),
+            Some((vec![0, 1], Some("2".into())))
+        );
+        assert_eq!(
+            paginate(items.clone(), Some("2"), 2),
+            Some((vec![2, 3], Some("4".into())))
+        );
+        assert_eq!(paginate(items, Some("4"), 2), Some((vec![4], None)));
+    }
+
+    #[test]
+    fn full_last_page_has_no_next_cursor() {
+        assert_eq!(
+            paginate(vec![0, 1, 2, 3], Some("2"), 2),
+            Some((vec![2, 3], None))
+        );
+        assert_eq!(
+            paginate(Vec::<u32>::new(), None, 2),
+            Some((Vec::new(), None))
+        );
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        assert_eq!(paginate(vec![0, 1, 2], Some("4"), 2), None);
+        assert_eq!(paginate(vec![0, 1, 2], Some("-1"), 2), None);
+        assert_eq!(paginate(vec![0, 1, 2], Some("next"), 2), None);
+        // A cursor at the end gives an empty last page
+        assert_eq!(
+            paginate(vec![0, 1, 2], Some("3"), 2),
+            Some((Vec::new(), None))
+        );
+    }
+}
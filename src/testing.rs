@@ -0,0 +1,294 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::error::{McpError, SERVER_ERROR};
+use crate::schema::original::{self, RequestId};
+use crate::schema::{Message, ServerResult, VERSION};
+use crate::server::{respond_to, ContentItem, MCPServer, Prompt, Tool, ValidationMiddleware};
+use crate::transport::{InMemoryTransport, Transport};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::runtime::Runtime;
+
+/// Calls a [`Tool`] directly, without a server or transport, so it can be
+/// tested as a black box
+pub struct ToolTestHarness {
+    tool: Box<dyn Tool>,
+    validation: ValidationMiddleware,
+}
+
+impl ToolTestHarness {
+    /// Constructor
+    pub fn new(tool: impl Tool + 'static) -> Self {
+        Self {
+            tool: Box::new(tool),
+            validation: ValidationMiddleware::new().panic_on_invalid(false),
+        }
+    }
+
+    /// Calls the tool with the given arguments, which must be a JSON object.
+    /// The result is validated as the server would before sending it, and a
+    /// failed call is returned as [`McpError::Rpc`].
+    pub async fn call_with(&mut self, args: Value) -> Result<Vec<ContentItem>, McpError> {
+        let arguments = serde_json::from_value(args)?;
+        let result = match self.tool.call(arguments).await {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(McpError::Rpc {
+                    code: SERVER_ERROR,
+                    message: err.message().into(),
+                })
+            }
+        };
+        let response = respond_to(
+            "2.0",
+            RequestId::from_int(0),
+            ServerResult::CallTool(original::CallToolResult {
+                content: result,
+                is_error: None,
+                meta: Default::default(),
+            }),
+        );
+        match self.validation.check(response, &RequestId::from_int(0)) {
+            Message::Response {
+                result: ServerResult::CallTool(result),
+                ..
+            } => Ok(result.content),
+            Message::Error(original::JsonrpcError { error, .. }) => Err(McpError::Rpc {
+                code: error.code,
+                message: error.message,
+            }),
+            _ => unreachable!("validation only ever replaces responses with errors"),
+        }
+    }
+
+    /// Returns the tool's input schema as JSON
+    pub fn schema(&self) -> Value {
+        serde_json::to_value(self.tool.input_schema()).expect("schemas serialize to JSON")
+    }
+
+    /// Panics unless the tool's input schema is a valid JSON Schema Draft 7
+    /// document
+    pub fn assert_schema_valid(&self) {
+        let schema = self.schema();
+        if let Err(err) = jsonschema::draft7::meta::validate(&schema) {
+            panic!("invalid input schema: {err}\n{schema:#}");
+        }
+    }
+}
+
+/// Fills in a [`Prompt`] directly, without a server or transport, so it can
+/// be tested as a black box
+///
+/// ```
+/// # use async_trait::async_trait;
+/// # use mcp::error::McpError;
+/// # use mcp::schema::original::{PromptMessage, Role};
+/// # use mcp::server::{text_message, Prompt, PromptArgument};
+/// # use mcp::testing::PromptTestHarness;
+/// # use std::collections::HashMap;
+/// struct Greet;
+///
+/// #[async_trait]
+/// impl Prompt for Greet {
+///     fn name(&self) -> &str {
+///         "greet"
+///     }
+///
+///     fn arguments(&self) -> Vec<PromptArgument> {
+///         vec![PromptArgument {
+///             name: "name".into(),
+///             required: true,
+///             ..Default::default()
+///         }]
+///     }
+///
+///     async fn get(
+///         &self,
+///         arguments: HashMap<String, String>,
+///     ) -> Result<Vec<PromptMessage>, McpError> {
+///         Ok(vec![text_message(Role::User, format!("Hi, {}!", arguments["name"]))])
+///     }
+/// }
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let mut harness = PromptTestHarness::new(Greet);
+/// harness.assert_arguments_complete(&[("name", "Jade")]);
+/// let messages = harness.get(HashMap::from([("name", "Jade")])).await.unwrap();
+/// assert_eq!(messages.len(), 1);
+/// # });
+/// ```
+pub struct PromptTestHarness {
+    prompt: Box<dyn Prompt>,
+    validation: ValidationMiddleware,
+}
+
+impl PromptTestHarness {
+    /// Constructor
+    pub fn new(prompt: impl Prompt + 'static) -> Self {
+        Self {
+            prompt: Box::new(prompt),
+            validation: ValidationMiddleware::new().panic_on_invalid(false),
+        }
+    }
+
+    /// Fills in the prompt with the given arguments. The result is validated
+    /// as the server would before sending it.
+    pub async fn get(
+        &mut self,
+        args: HashMap<&str, &str>,
+    ) -> Result<Vec<original::PromptMessage>, McpError> {
+        let arguments = args
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        let messages = self.prompt.get(arguments).await?;
+        let response = respond_to(
+            "2.0",
+            RequestId::from_int(0),
+            ServerResult::GetPrompt(original::GetPromptResult {
+                description: self.prompt.description().map(String::from),
+                messages,
+                meta: Default::default(),
+            }),
+        );
+        match self.validation.check(response, &RequestId::from_int(0)) {
+            Message::Response {
+                result: ServerResult::GetPrompt(result),
+                ..
+            } => Ok(result.messages),
+            Message::Error(original::JsonrpcError { error, .. }) => Err(McpError::Rpc {
+                code: error.code,
+                message: error.message,
+            }),
+            _ => unreachable!("validation only ever replaces responses with errors"),
+        }
+    }
+
+    /// Panics unless every required argument of the prompt is given
+    pub fn assert_arguments_complete(&self, args: &[(&str, &str)]) {
+        let missing = self
+            .prompt
+            .arguments()
+            .into_iter()
+            .filter(|argument| argument.required && argument.default.is_none())
+            .filter(|argument| !args.iter().any(|(name, _)| *name == argument.name))
+            .map(|argument| argument.name)
+            .collect::<Vec<_>>();
+        assert!(
+            missing.is_empty(),
+            "missing required arguments for prompt {}: {missing:?}",
+            self.prompt.name()
+        );
+    }
+}
+
+/// Drives an [`MCPServer`] over an in-memory transport with blocking calls,
+/// so tests don't need to be async. The server runs on the client's own
+/// runtime, so it must not be used from within another one.
+pub struct TestClient {
+    runtime: Runtime,
+    transport: InMemoryTransport,
+    next_id: i64,
+}
+
+impl TestClient {
+    /// Starts the server built by `server` on the other end of the client's
+    /// transport
+    pub fn new(server: impl FnOnce(InMemoryTransport) -> MCPServer<InMemoryTransport>) -> Self {
+        let runtime = Runtime::new().expect("failed to start a runtime");
+        let (server_end, transport) = InMemoryTransport::pair();
+        let server = server(server_end);
+        runtime.spawn(async move { server.run().await });
+        Self {
+            runtime,
+            transport,
+            next_id: 0,
+        }
+    }
+
+    /// Initializes the connection, panicking if the server refuses
+    pub fn initialize(&mut self) -> original::InitializeResult {
+        let result = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "test-client", "version": "0.0.0" },
+                }),
+            )
+            .expect("initialize failed");
+        self.notify("notifications/initialized", json!({}));
+        result
+    }
+
+    /// Lists the first page of tools, panicking on error
+    pub fn list_tools(&mut self) -> original::ListToolsResult {
+        self.request("tools/list", json!({}))
+            .expect("tools/list failed")
+    }
+
+    /// Calls a tool, panicking if the server answers with an error rather
+    /// than a result
+    pub fn call_tool(&mut self, name: &str, args: Value) -> original::CallToolResult {
+        self.request("tools/call", json!({ "name": name, "arguments": args }))
+            .expect("tools/call failed")
+    }
+
+    /// Sends a request and waits for its result, skipping any other messages
+    /// from the server. An error response is returned as [`McpError::Rpc`].
+    pub fn request<R: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> Result<R, McpError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let msg = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let transport = &mut self.transport;
+        let mut response: Value = self.runtime.block_on(async {
+            transport.send(&serde_json::to_vec(&msg)?).await?;
+            loop {
+                let buf = transport.recv().await?;
+                if buf.is_empty() {
+                    return Err(McpError::Disconnected);
+                }
+                let response: Value = serde_json::from_slice(&buf)?;
+                if response["id"] == id {
+                    return Ok(response);
+                }
+            }
+        })?;
+        if let Some(error) = response.get("error") {
+            return Err(McpError::Rpc {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().into(),
+            });
+        }
+        Ok(serde_json::from_value(response["result"].take())?)
+    }
+
+    /// Sends a notification
+    pub fn notify(&mut self, method: &str, params: Value) {
+        let msg = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        let transport = &mut self.transport;
+        self.runtime
+            .block_on(async { transport.send(&serde_json::to_vec(&msg).unwrap()).await })
+            .expect("failed to send notification");
+    }
+}
@@ -0,0 +1,64 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Schemas registered for each type, keyed by its full type name
+static SCHEMAS: OnceLock<RwLock<HashMap<&'static str, Map<String, Value>>>> = OnceLock::new();
+
+/// JSON Schema fragments for types `ToolQuery` can't describe on its own,
+/// such as those from other crates. A registered type is described by its
+/// fragment in place of a plain object.
+///
+/// ```
+/// # use mcp::tool_schema::ToolSchemaRegistry;
+/// # use serde_json::json;
+/// struct NaiveDate;
+///
+/// ToolSchemaRegistry::register::<NaiveDate>(json!({ "type": "string", "format": "date" }));
+/// assert_eq!(ToolSchemaRegistry::get::<NaiveDate>().unwrap()["format"], "date");
+/// ```
+pub struct ToolSchemaRegistry;
+
+impl ToolSchemaRegistry {
+    /// Describes a type with the given schema, which must be a JSON object.
+    /// Schemas are read when a `ToolQuery` schema is generated, so register
+    /// them before then.
+    pub fn register<T: ?Sized>(schema: Value) {
+        let Value::Object(schema) = schema else {
+            panic!(
+                "schema for {} must be a JSON object",
+                std::any::type_name::<T>()
+            );
+        };
+        SCHEMAS
+            .get_or_init(RwLock::default)
+            .write()
+            .unwrap()
+            .insert(std::any::type_name::<T>(), schema);
+    }
+
+    /// Returns the schema registered for a type, if any
+    pub fn get<T: ?Sized>() -> Option<Map<String, Value>> {
+        SCHEMAS
+            .get()?
+            .read()
+            .unwrap()
+            .get(std::any::type_name::<T>())
+            .cloned()
+    }
+}
@@ -0,0 +1,443 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Stdin, Stdout};
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+
+/// HTTP+SSE transport
+#[cfg(feature = "http-sse")]
+mod http_sse;
+#[cfg(feature = "http-sse")]
+pub use http_sse::{health_router, HttpSseServerTransport};
+/// HTTP+SSE transport, client side
+#[cfg(feature = "http-sse-client")]
+mod http_sse_client;
+#[cfg(feature = "http-sse-client")]
+pub use http_sse_client::HttpSseClientTransport;
+
+#[async_trait]
+pub trait Transport {
+    /// Receives and stores a message from the transport. This must be cancel
+    /// safe, since the server stops waiting on it to send its own messages.
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error>;
+    /// Sends a messsage on the transport as bytes
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error>;
+    /// Returns the next message without consuming it, so the following
+    /// `recv` returns the same message. Not every transport supports this.
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "transport does not support peeking",
+        ))
+    }
+    /// Re-establishes a broken connection. Not every transport supports this.
+    async fn reconnect(&mut self) -> Result<(), io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "transport does not support reconnecting",
+        ))
+    }
+}
+
+/// MCP transport using stdio
+pub struct StdioTransport {
+    stdin: BufReader<Stdin>,
+    stdout: Stdout,
+    /// Partially read message, kept here so `recv` is cancel safe
+    buf: Vec<u8>,
+    /// Message read by `peek` but not yet received
+    peeked: Option<Vec<u8>>,
+}
+
+impl StdioTransport {
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            stdin: BufReader::new(tokio::io::stdin()),
+            stdout: tokio::io::stdout(),
+            buf: Vec::new(),
+            peeked: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    /// Receives a message from the transport as bytes
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        if let Some(msg) = self.peeked.take() {
+            return Ok(msg);
+        }
+        // Read a line from stdin
+        self.stdin.read_until(b'\n', &mut self.buf).await?;
+        Ok(std::mem::take(&mut self.buf))
+    }
+    /// Sends a messsage on the transport as bytes
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        self.stdout.write_all(buf).await?;
+        self.stdout.write_u8(b'\n').await?;
+        self.stdout.flush().await
+    }
+    /// Reads the next message and holds it for the following `recv`
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        if self.peeked.is_none() {
+            self.stdin.read_until(b'\n', &mut self.buf).await?;
+            self.peeked = Some(std::mem::take(&mut self.buf));
+        }
+        Ok(self.peeked.as_deref().unwrap_or_default())
+    }
+}
+
+/// MCP transport within a single process, for tests and embedding. Messages
+/// sent on one end of a pair are received on the other.
+pub struct InMemoryTransport {
+    tx: mpsc::UnboundedSender<Vec<u8>>,
+    rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    /// Message read by `peek` but not yet received
+    peeked: Option<Vec<u8>>,
+}
+
+impl InMemoryTransport {
+    /// Creates a connected pair of transports
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, b_rx) = mpsc::unbounded_channel();
+        let (b_tx, a_rx) = mpsc::unbounded_channel();
+        (
+            Self {
+                tx: a_tx,
+                rx: a_rx,
+                peeked: None,
+            },
+            Self {
+                tx: b_tx,
+                rx: b_rx,
+                peeked: None,
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl Transport for InMemoryTransport {
+    /// Receives a message from the other end, or an empty message once it
+    /// has been dropped
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        if let Some(msg) = self.peeked.take() {
+            return Ok(msg);
+        }
+        Ok(self.rx.recv().await.unwrap_or_default())
+    }
+    /// Sends a message to the other end
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        self.tx
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+    /// Receives the next message and holds it for the following `recv`
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.rx.recv().await.unwrap_or_default());
+        }
+        Ok(self.peeked.as_deref().unwrap_or_default())
+    }
+}
+
+/// Wraps a transport, retrying failed sends with exponential backoff and
+/// reconnecting the inner transport when a receive fails
+pub struct RetryableTransport<T: Transport> {
+    inner: T,
+    /// Number of times to retry before giving up
+    max_retries: u32,
+    /// Delay before the first retry, doubled after each one
+    initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    max_backoff: Duration,
+}
+
+impl<T: Transport> RetryableTransport<T> {
+    /// Constructor. Retries 3 times, starting at 100ms apart.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the number of times to retry before giving up
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the upper bound on the delay between retries
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Returns the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Send> Transport for RetryableTransport<T> {
+    /// Receives a message, reconnecting the inner transport if it fails.
+    /// This is only cancel safe if the inner transport's `reconnect` is.
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        let mut retries = 0;
+        loop {
+            match self.inner.recv().await {
+                Ok(msg) => return Ok(msg),
+                Err(err) => {
+                    if retries == self.max_retries || self.inner.reconnect().await.is_err() {
+                        return Err(err);
+                    }
+                    retries += 1;
+                    tracing::warn!(%err, retries, "receive failed, reconnected");
+                }
+            }
+        }
+    }
+    /// Sends a message, retrying with exponential backoff if it fails. The
+    /// original error is returned once every retry has failed.
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        let mut backoff = self.initial_backoff;
+        let mut first_err = None;
+        for retries in 0..=self.max_retries {
+            if retries > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(self.max_backoff);
+            }
+            match self.inner.send(buf).await {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    tracing::warn!(%err, retries, "send failed");
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        Err(first_err.expect("send is attempted at least once"))
+    }
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.peek().await
+    }
+    async fn reconnect(&mut self) -> Result<(), io::Error> {
+        self.inner.reconnect().await
+    }
+}
+
+/// Counts of traffic through a [`TransportMetrics`], at a point in time
+#[derive(Debug, Clone, Default)]
+pub struct TransportSnapshot {
+    pub sent_bytes: u64,
+    pub recv_bytes: u64,
+    pub sent_messages: u64,
+    pub recv_messages: u64,
+    /// When the last message was received, if any has been
+    pub last_recv_at: Option<Instant>,
+    /// When the last message was sent, if any has been
+    pub last_sent_at: Option<Instant>,
+}
+
+/// Wraps a transport, counting the bytes and messages that pass through it
+/// in each direction
+pub struct TransportMetrics<T: Transport> {
+    inner: T,
+    /// Shared with readers, so the counts can be watched after the transport
+    /// is handed to a server
+    snapshot: Arc<Mutex<TransportSnapshot>>,
+}
+
+impl<T: Transport> TransportMetrics<T> {
+    /// Constructor
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            snapshot: Arc::default(),
+        }
+    }
+
+    /// Returns the counts so far
+    pub fn metrics(&self) -> TransportSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+
+    /// Returns a handle for reading the counts after the transport has been
+    /// moved elsewhere
+    pub fn reader(&self) -> TransportMetricsReader {
+        TransportMetricsReader {
+            snapshot: Arc::clone(&self.snapshot),
+        }
+    }
+
+    /// Returns the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Reads the counts of a [`TransportMetrics`] from elsewhere
+#[derive(Debug, Clone)]
+pub struct TransportMetricsReader {
+    snapshot: Arc<Mutex<TransportSnapshot>>,
+}
+
+impl TransportMetricsReader {
+    /// Returns the counts so far
+    pub fn metrics(&self) -> TransportSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl<T: Transport + Send> Transport for TransportMetrics<T> {
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        let msg = self.inner.recv().await?;
+        // An empty read is the peer closing, not a message
+        if !msg.is_empty() {
+            let mut snapshot = self.snapshot.lock().unwrap();
+            snapshot.recv_bytes += msg.len() as u64;
+            snapshot.recv_messages += 1;
+            snapshot.last_recv_at = Some(Instant::now());
+        }
+        Ok(msg)
+    }
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        self.inner.send(buf).await?;
+        let mut snapshot = self.snapshot.lock().unwrap();
+        snapshot.sent_bytes += buf.len() as u64;
+        snapshot.sent_messages += 1;
+        snapshot.last_sent_at = Some(Instant::now());
+        Ok(())
+    }
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        self.inner.peek().await
+    }
+    async fn reconnect(&mut self) -> Result<(), io::Error> {
+        self.inner.reconnect().await
+    }
+}
+
+/// Wraps a transport, holding back incoming requests once a number of them
+/// are waiting on responses, so a client sending faster than the server can
+/// answer can't grow the queue without bound. Notifications and responses
+/// are never held back, so the server can always read cancellations and
+/// answers to its own requests.
+pub struct BackpressureTransport<T: Transport> {
+    inner: T,
+    permits: Arc<Semaphore>,
+    /// One permit per request received but not yet answered, keyed by the
+    /// request's ID as JSON
+    in_flight: HashMap<String, OwnedSemaphorePermit>,
+    /// Request read from the inner transport while waiting for a permit,
+    /// kept here so `recv` is cancel safe
+    held: Option<(String, Vec<u8>)>,
+}
+
+impl<T: Transport> BackpressureTransport<T> {
+    /// Constructor. At most `max_in_flight` requests are received before
+    /// responses to them are sent.
+    pub fn new(inner: T, max_in_flight: usize) -> Self {
+        Self {
+            inner,
+            permits: Arc::new(Semaphore::new(max_in_flight)),
+            in_flight: HashMap::new(),
+            held: None,
+        }
+    }
+
+    /// Returns the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Parts of a JSON-RPC message that tell requests and responses apart
+#[derive(serde::Deserialize)]
+struct MessageShape {
+    #[serde(default)]
+    method: Option<serde::de::IgnoredAny>,
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+}
+
+/// Returns the ID of a JSON-RPC message as JSON, if it is a request, or a
+/// response when `response` is set. Messages without an ID, or with a null
+/// one, can't be matched up.
+fn message_id(msg: &[u8], response: bool) -> Option<String> {
+    let shape: MessageShape = serde_json::from_slice(msg).ok()?;
+    if shape.method.is_some() == response {
+        return None;
+    }
+    shape.id.filter(|id| !id.is_null()).map(|id| id.to_string())
+}
+
+#[async_trait]
+impl<T: Transport + Send> Transport for BackpressureTransport<T> {
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        let (id, msg) = match self.held.take() {
+            Some(held) => held,
+            None => {
+                let msg = self.inner.recv().await?;
+                match message_id(&msg, false) {
+                    Some(id) => (id, msg),
+                    // Only requests are ever answered, so only they wait
+                    None => return Ok(msg),
+                }
+            }
+        };
+        // Hold on to the request while waiting, so it isn't lost if this is
+        // cancelled
+        self.held = Some((id, msg));
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let (id, msg) = self.held.take().expect("request is held while waiting");
+        self.in_flight.insert(id, permit);
+        Ok(msg)
+    }
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        // The request is answered whether or not the response gets through,
+        // so its permit is released either way
+        let _permit = message_id(buf, true).and_then(|id| self.in_flight.remove(&id));
+        self.inner.send(buf).await
+    }
+    async fn peek(&mut self) -> Result<&[u8], io::Error> {
+        match &self.held {
+            Some((_, msg)) => Ok(msg),
+            None => self.inner.peek().await,
+        }
+    }
+    async fn reconnect(&mut self) -> Result<(), io::Error> {
+        self.inner.reconnect().await
+    }
+}
@@ -0,0 +1,238 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::Transport;
+use crate::server::MCPServer;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::convert::Infallible;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Path clients POST messages to
+const MESSAGE_PATH: &str = "/message";
+/// Path clients open the event stream at
+const SSE_PATH: &str = "/sse";
+/// Path serving the server's health
+const HEALTH_PATH: &str = "/health";
+
+/// State shared between the transport and its HTTP handlers
+struct SseState {
+    /// Messages POSTed by the client
+    incoming: mpsc::UnboundedSender<Vec<u8>>,
+    /// Event stream of the connected client, if any
+    outgoing: Mutex<Option<mpsc::UnboundedSender<Vec<u8>>>>,
+    /// Number of event streams opened, identifying the current one
+    streams_opened: AtomicU64,
+    /// URL advertised to the client for POSTing messages
+    endpoint: String,
+}
+
+/// MCP transport over HTTP with Server-Sent Events, serving one client. The
+/// client opens an event stream with `GET /sse`, which carries the server's
+/// messages, and sends its own with `POST /message`.
+pub struct HttpSseServerTransport {
+    rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    state: Arc<SseState>,
+}
+
+impl HttpSseServerTransport {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::with_endpoint(MESSAGE_PATH)
+    }
+
+    /// Constructor advertising a different URL for POSTing messages, such as
+    /// when the router is nested under a prefix
+    pub fn with_endpoint(endpoint: &str) -> Self {
+        let (incoming, rx) = mpsc::unbounded_channel();
+        Self {
+            rx,
+            state: Arc::new(SseState {
+                incoming,
+                outgoing: Mutex::new(None),
+                streams_opened: AtomicU64::new(0),
+                endpoint: endpoint.into(),
+            }),
+        }
+    }
+
+    /// Returns the routes serving this transport, to run on their own or
+    /// merge into a larger application
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route(SSE_PATH, get(open_stream))
+            .route(MESSAGE_PATH, post(receive_message))
+            .with_state(Arc::clone(&self.state))
+    }
+}
+
+impl Default for HttpSseServerTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the media types a header lists, without their parameters
+fn media_types(headers: &HeaderMap, name: header::HeaderName) -> Vec<&str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|media_type| media_type.split(';').next().unwrap_or_default().trim())
+        .collect()
+}
+
+/// Returns a route serving a server's health as JSON at `GET /health`, to
+/// merge alongside [`HttpSseServerTransport::router`]
+pub fn health_router<T>(server: Arc<MCPServer<T>>) -> Router
+where
+    T: Transport + Send + Sync + 'static,
+{
+    Router::new()
+        .route(HEALTH_PATH, get(health::<T>))
+        .with_state(server)
+}
+
+/// Reports a server's health
+async fn health<T>(State(server): State<Arc<MCPServer<T>>>) -> Json<serde_json::Value>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    let health = server.health().await;
+    Json(serde_json::json!({
+        "uptime_secs": health.uptime.as_secs_f64(),
+        "requests_handled": health.requests_handled,
+        "tools_registered": health.tools_registered,
+        "resources_registered": health.resources_registered,
+        "prompts_registered": health.prompts_registered,
+        "client_connected": health.client_connected,
+        "secs_since_last_request": health
+            .last_request_at
+            .map(|at| at.elapsed().as_secs_f64()),
+    }))
+}
+
+/// Held by an event stream, telling the transport the client is gone when
+/// the stream is dropped, unless a newer stream has replaced it
+struct StreamGuard {
+    state: Arc<SseState>,
+    stream: u64,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        if self.state.streams_opened.load(Ordering::SeqCst) == self.stream {
+            *self.state.outgoing.lock().unwrap() = None;
+            // An empty message is read as a disconnect
+            let _ = self.state.incoming.send(Vec::new());
+        }
+    }
+}
+
+/// Opens the event stream, replacing any earlier one. The first event tells
+/// the client where to POST its messages. Clients that don't accept an
+/// event stream are refused.
+async fn open_stream(
+    State(state): State<Arc<SseState>>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    // A missing Accept header accepts anything
+    let accepted = media_types(&headers, header::ACCEPT);
+    if !accepted.is_empty()
+        && !accepted.iter().any(|media_type| {
+            ["text/event-stream", "text/*", "*/*"]
+                .iter()
+                .any(|allowed| media_type.eq_ignore_ascii_case(allowed))
+        })
+    {
+        return Err(StatusCode::NOT_ACCEPTABLE);
+    }
+    let (tx, rx) = mpsc::unbounded_channel();
+    *state.outgoing.lock().unwrap() = Some(tx);
+    let guard = StreamGuard {
+        stream: state.streams_opened.fetch_add(1, Ordering::SeqCst) + 1,
+        state: Arc::clone(&state),
+    };
+    let endpoint = Event::default().event("endpoint").data(&state.endpoint);
+    let messages = UnboundedReceiverStream::new(rx).map(move |msg: Vec<u8>| {
+        let _ = &guard;
+        Ok(Event::default()
+            .event("message")
+            .data(String::from_utf8_lossy(&msg)))
+    });
+    Ok(Sse::new(tokio_stream::once(Ok(endpoint)).chain(messages)).keep_alive(KeepAlive::default()))
+}
+
+/// Accepts a message from the client, to be returned by `recv`. Messages
+/// that aren't sent as JSON, or don't parse as JSON, are refused.
+async fn receive_message(
+    State(state): State<Arc<SseState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if !media_types(&headers, header::CONTENT_TYPE)
+        .first()
+        .is_some_and(|media_type| media_type.eq_ignore_ascii_case("application/json"))
+    {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+    }
+    if serde_json::from_slice::<serde::de::IgnoredAny>(&body).is_err() {
+        return StatusCode::BAD_REQUEST;
+    }
+    match state.incoming.send(body.to_vec()) {
+        Ok(()) => StatusCode::ACCEPTED,
+        // The transport has been dropped
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[async_trait]
+impl Transport for HttpSseServerTransport {
+    /// Receives a message POSTed by the client, or an empty message once
+    /// the client closes its event stream
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        // The state holds a sender, so this never runs dry
+        Ok(self.rx.recv().await.unwrap_or_default())
+    }
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        let mut outgoing = self.state.outgoing.lock().unwrap();
+        let Some(tx) = outgoing.as_ref() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "no client has opened the event stream",
+            ));
+        };
+        if tx.send(buf.to_vec()).is_err() {
+            *outgoing = None;
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "client closed the event stream",
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::Transport;
+use crate::error::McpError;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::Url;
+use std::io;
+use std::pin::Pin;
+use tokio_stream::{Stream, StreamExt};
+
+/// Body of the event stream, as it arrives
+type EventStream = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
+
+/// MCP transport connecting to an HTTP server with Server-Sent Events. The
+/// server's messages arrive on an event stream, and the client's are POSTed
+/// to the endpoint the server names when the stream opens.
+pub struct HttpSseClientTransport {
+    http: reqwest::Client,
+    /// URL to POST messages to
+    endpoint: Url,
+    events: EventStream,
+    /// Bytes of the event stream not yet parsed into events
+    buffer: Vec<u8>,
+}
+
+/// A single Server-Sent Event
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+impl HttpSseClientTransport {
+    /// Opens the event stream at `<base_url>/sse` and waits for the server
+    /// to say where to POST messages
+    pub async fn connect(base_url: &str) -> Result<Self, McpError> {
+        let mut base = Url::parse(base_url).map_err(io::Error::other)?;
+        // Without a trailing slash, joining would replace the last segment
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        let http = reqwest::Client::new();
+        let response = http
+            .get(base.join("sse").map_err(io::Error::other)?)
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(io::Error::other)?;
+        let mut transport = Self {
+            http,
+            endpoint: base.clone(),
+            events: Box::pin(response.bytes_stream()),
+            buffer: Vec::new(),
+        };
+        transport.endpoint = match transport.next_event().await? {
+            Some(SseEvent { event, data }) if event == "endpoint" => {
+                base.join(data.trim()).map_err(io::Error::other)?
+            }
+            _ => {
+                return Err(McpError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "server did not send an endpoint event",
+                )))
+            }
+        };
+        Ok(transport)
+    }
+
+    /// Reads the next event from the stream, or `None` once it ends. This is
+    /// cancel safe, as partial events are kept in the buffer.
+    async fn next_event(&mut self) -> Result<Option<SseEvent>, io::Error> {
+        loop {
+            if let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+                let raw = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+                if let Some(event) = parse_event(&String::from_utf8_lossy(&raw)) {
+                    return Ok(Some(event));
+                }
+                continue;
+            }
+            match self.events.next().await {
+                // Line endings may be CRLF, but JSON escapes any literal CR
+                Some(chunk) => self.buffer.extend(
+                    chunk
+                        .map_err(io::Error::other)?
+                        .iter()
+                        .filter(|&&byte| byte != b'\r'),
+                ),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Parses the lines of one event, ignoring comments and unknown fields.
+/// Returns `None` for an event with no data, such as a keep-alive.
+fn parse_event(raw: &str) -> Option<SseEvent> {
+    let mut event = "message".to_string();
+    let mut data = Vec::new();
+    for line in raw.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.into(),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+    (!data.is_empty()).then(|| SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+#[async_trait]
+impl Transport for HttpSseClientTransport {
+    /// Receives the next message from the event stream, or an empty message
+    /// once the server closes it
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        loop {
+            match self.next_event().await? {
+                Some(SseEvent { event, data }) if event == "message" => {
+                    return Ok(data.into_bytes())
+                }
+                Some(_) => continue,
+                None => return Ok(Vec::new()),
+            }
+        }
+    }
+    async fn send(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        self.http
+            .post(self.endpoint.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(buf.to_vec())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use crate::server::{PromptRegistry, ResourceRegistry, ToolRegistry};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// Content a tool call can return, as TypeScript types
+const CONTENT_TYPES: &str = r#"export interface TextContent {
+  type: "text";
+  text: string;
+}
+
+export interface ImageContent {
+  type: "image";
+  data: string;
+  mimeType: string;
+}
+
+export interface EmbeddedResource {
+  type: "resource";
+  resource: ResourceContents;
+}
+
+export type ContentItem = TextContent | ImageContent | EmbeddedResource;
+
+export type ResourceContents =
+  | { uri: string; mimeType?: string; text: string }
+  | { uri: string; mimeType?: string; blob: string };
+
+export interface PromptMessage {
+  role: "user" | "assistant";
+  content: ContentItem;
+}
+"#;
+
+/// Names declared by [`CONTENT_TYPES`], and words TypeScript reserves, which
+/// generated names must avoid
+const TAKEN: &[&str] = &[
+    "TextContent",
+    "ImageContent",
+    "EmbeddedResource",
+    "ContentItem",
+    "ResourceContents",
+    "PromptMessage",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Renders TypeScript bindings for a server's tools, resources and prompts.
+/// Each tool gets an interface for its arguments, named after the tool, and
+/// each tool, resource and prompt a function signature. Names that would
+/// clash once converted are numbered, in the order they are declared.
+pub async fn bindings(
+    tools: &ToolRegistry,
+    resources: &ResourceRegistry,
+    prompts: &PromptRegistry,
+) -> String {
+    let mut out = String::from(CONTENT_TYPES);
+    let mut identifiers = Identifiers::default();
+    let tools = tools.items().await;
+    let mut names: Vec<_> = tools.keys().collect();
+    names.sort();
+    for name in names {
+        let tool = &tools[name];
+        let schema = tool.input_schema();
+        let name = identifiers.claim(name, |name| {
+            vec![camel_case(name), format!("{name}Arguments")]
+        });
+        let arguments = format!("{name}Arguments");
+        let mut properties: Vec<_> = schema.properties.iter().collect();
+        properties.sort_by_key(|(property, _)| *property);
+        out.push_str(&format!("\nexport interface {arguments} {{\n"));
+        for (property, property_schema) in properties {
+            let optional = if schema.required.contains(property) {
+                ""
+            } else {
+                "?"
+            };
+            write_doc(
+                &mut out,
+                "  ",
+                property_schema.get("description").and_then(Value::as_str),
+            );
+            out.push_str(&format!(
+                "  {}{optional}: {};\n",
+                property_name(property),
+                schema_type(property_schema)
+            ));
+        }
+        out.push_str("}\n\n");
+        write_doc(&mut out, "", tool.description());
+        out.push_str(&format!(
+            "export declare function {}(args: {arguments}): Promise<ContentItem[]>;\n",
+            camel_case(&name)
+        ));
+    }
+    let resources = resources.items().await;
+    let mut resources: Vec<_> = resources.values().collect();
+    resources.sort_by(|a, b| a.uri().cmp(b.uri()));
+    for resource in resources {
+        let name = identifiers.claim(resource.name(), |name| vec![format!("read{name}")]);
+        out.push('\n');
+        write_doc(&mut out, "", Some(resource.uri()));
+        out.push_str(&format!(
+            "export declare function read{name}(): Promise<ResourceContents[]>;\n"
+        ));
+    }
+    let prompts = prompts.items().await;
+    let mut names: Vec<_> = prompts.keys().collect();
+    names.sort();
+    for name in names {
+        let prompt = &prompts[name];
+        let name = identifiers.claim(name, |name| {
+            vec![format!("get{name}"), format!("{name}PromptArguments")]
+        });
+        let arguments = format!("{name}PromptArguments");
+        out.push_str(&format!("\nexport interface {arguments} {{\n"));
+        for argument in prompt.arguments() {
+            // Arguments with a default can be left out even when required
+            let optional = if argument.required && argument.default.is_none() {
+                ""
+            } else {
+                "?"
+            };
+            write_doc(&mut out, "  ", argument.description.as_deref());
+            out.push_str(&format!(
+                "  {}{optional}: string;\n",
+                property_name(&argument.name)
+            ));
+        }
+        out.push_str("}\n\n");
+        write_doc(&mut out, "", prompt.description());
+        out.push_str(&format!(
+            "export declare function get{name}(args: {arguments}): Promise<PromptMessage[]>;\n"
+        ));
+    }
+    out
+}
+
+/// Names already declared in the bindings
+struct Identifiers(HashSet<String>);
+
+impl Default for Identifiers {
+    fn default() -> Self {
+        Self(TAKEN.iter().map(|name| name.to_string()).collect())
+    }
+}
+
+impl Identifiers {
+    /// Converts `name` to PascalCase and declares the identifiers `declared`
+    /// makes from it, numbering the name if any of them are already taken
+    fn claim(&mut self, name: &str, declared: impl Fn(&str) -> Vec<String>) -> String {
+        let base = pascal_case(name);
+        let mut name = base.clone();
+        for n in 2.. {
+            let identifiers = declared(&name);
+            if !identifiers
+                .iter()
+                .any(|identifier| self.0.contains(identifier))
+            {
+                self.0.extend(identifiers);
+                break;
+            }
+            name = format!("{base}{n}");
+        }
+        name
+    }
+}
+
+/// Returns the TypeScript type matching a JSON schema
+fn schema_type(schema: &Map<String, Value>) -> String {
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        return union(values.iter().map(Value::to_string));
+    }
+    match schema.get("type") {
+        Some(Value::String(type_)) => json_type(type_, schema),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|type_| json_type(type_, schema)),
+        ),
+        _ => "unknown".into(),
+    }
+}
+
+/// Returns the TypeScript type matching one JSON schema type
+fn json_type(type_: &str, schema: &Map<String, Value>) -> String {
+    match type_ {
+        "string" => "string".into(),
+        "number" | "integer" => "number".into(),
+        "boolean" => "boolean".into(),
+        "null" => "null".into(),
+        "array" => match schema.get("items") {
+            Some(Value::Object(items)) => format!("Array<{}>", schema_type(items)),
+            _ => "unknown[]".into(),
+        },
+        "object" => match schema.get("properties") {
+            Some(Value::Object(properties)) if !properties.is_empty() => {
+                let required = schema.get("required").and_then(Value::as_array);
+                let fields = properties.iter().map(|(name, property)| {
+                    let optional = if required
+                        .is_some_and(|required| required.iter().any(|required| required == name))
+                    {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    let type_ = match property {
+                        Value::Object(property) => schema_type(property),
+                        _ => "unknown".into(),
+                    };
+                    format!("{}{optional}: {type_}", property_name(name))
+                });
+                format!("{{ {} }}", fields.collect::<Vec<_>>().join("; "))
+            }
+            _ => "Record<string, unknown>".into(),
+        },
+        _ => "unknown".into(),
+    }
+}
+
+/// Joins types into a union, or `never` if there are none
+fn union(types: impl Iterator<Item = String>) -> String {
+    let types: Vec<_> = types.collect();
+    if types.is_empty() {
+        "never".into()
+    } else {
+        types.join(" | ")
+    }
+}
+
+/// Writes a doc comment, if there is anything to say
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    if let Some(doc) = doc.filter(|doc| !doc.is_empty()) {
+        out.push_str(&format!("{indent}/** {} */\n", doc.replace("*/", "*\\/")));
+    }
+}
+
+/// Returns a property name, quoted unless it is a valid identifier
+fn property_name(name: &str) -> String {
+    if is_identifier(name) {
+        name.into()
+    } else {
+        Value::from(name).to_string()
+    }
+}
+
+/// Returns whether a name can be used as is in TypeScript
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Converts a name such as `get_weather` or `file-list` to `GetWeather` or
+/// `FileList`. Letters outside ASCII are kept, as TypeScript allows them.
+fn pascal_case(name: &str) -> String {
+    let mut out: String = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+                .collect::<String>()
+        })
+        .collect();
+    if !out.starts_with(char::is_alphabetic) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// Converts a PascalCase name such as `GetWeather` to `getWeather`
+fn camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .into_iter()
+        .flat_map(char::to_lowercase)
+        .chain(chars)
+        .collect()
+}
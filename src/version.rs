@@ -0,0 +1,72 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// Also included by build.rs, so this must not depend on the rest of the crate
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+/// A revision of the MCP schema
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaVersion {
+    /// The 2024-11-05 revision
+    V2024_11_05,
+    /// The 2025-03-26 revision
+    V2025_03_26,
+}
+
+impl SchemaVersion {
+    /// Every supported revision, oldest first
+    pub const ALL: [Self; 2] = [Self::V2024_11_05, Self::V2025_03_26];
+
+    /// Returns the date string naming this revision, e.g. `"2024-11-05"`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::V2024_11_05 => "2024-11-05",
+            Self::V2025_03_26 => "2025-03-26",
+        }
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = ParseSchemaVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|version| version.as_str() == s)
+            .ok_or_else(|| ParseSchemaVersionError(s.to_string()))
+    }
+}
+
+/// Error returned when a string does not name a supported schema revision
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSchemaVersionError(String);
+
+impl fmt::Display for ParseSchemaVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown schema version {:?}", self.0)
+    }
+}
+
+impl core::error::Error for ParseSchemaVersionError {}
@@ -0,0 +1,255 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::error::McpError;
+use crate::schema::{original, Message, ServerMessage, ServerNotification};
+use crate::server::MCPServer;
+use crate::transport::Transport;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Watches the source of a resource, calling back whenever it changes
+pub trait ResourceWatcher {
+    /// Starts watching the resource with the given URI, until the returned
+    /// handle is dropped
+    fn watch(
+        &self,
+        uri: &str,
+        callback: Box<dyn Fn() + Send + 'static>,
+    ) -> Result<WatchHandle, McpError>;
+}
+
+/// Keeps a watch alive. Watching stops when this is dropped.
+pub struct WatchHandle {
+    _guard: Box<dyn Send>,
+}
+
+impl WatchHandle {
+    /// Wraps a value that stops watching when dropped
+    pub fn new(guard: impl Send + 'static) -> Self {
+        Self {
+            _guard: Box::new(guard),
+        }
+    }
+}
+
+/// Watches `file://` resources for changes on disk
+#[derive(Debug, Clone, Default)]
+pub struct FilesystemResourceWatcher {
+    /// Directory relative paths are resolved against, if not the working
+    /// directory
+    root: Option<PathBuf>,
+}
+
+impl FilesystemResourceWatcher {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves relative paths against the given directory
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Returns the path a URI refers to
+    fn path(&self, uri: &str) -> Result<PathBuf, McpError> {
+        let Some(path) = uri.strip_prefix("file://") else {
+            return Err(McpError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("not a file URI: {uri}"),
+            )));
+        };
+        Ok(match &self.root {
+            Some(root) => root.join(path),
+            None => Path::new(path).to_path_buf(),
+        })
+    }
+}
+
+impl ResourceWatcher for FilesystemResourceWatcher {
+    fn watch(
+        &self,
+        uri: &str,
+        callback: Box<dyn Fn() + Send + 'static>,
+    ) -> Result<WatchHandle, McpError> {
+        let path = self.path(uri)?;
+        let mut watcher: RecommendedWatcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                // Reads don't change the contents
+                Ok(event) if event.kind.is_access() => {}
+                Ok(_) => callback(),
+                Err(err) => tracing::warn!(%err, "resource watch failed"),
+            })
+            .map_err(io::Error::other)?;
+        watcher
+            .watch(&path, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+        Ok(WatchHandle::new(watcher))
+    }
+}
+
+impl<T> MCPServer<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    /// Watches a resource, notifying subscribed clients whenever it changes.
+    /// Must be called from within a tokio runtime.
+    pub fn watch_resource(
+        self: &Arc<Self>,
+        watcher: &impl ResourceWatcher,
+        uri: &str,
+    ) -> Result<WatchHandle, McpError> {
+        let server = Arc::downgrade(self);
+        let runtime = tokio::runtime::Handle::current();
+        let owned_uri = uri.to_string();
+        watcher.watch(
+            uri,
+            Box::new(move || {
+                let Some(server) = server.upgrade() else {
+                    return;
+                };
+                let uri = owned_uri.clone();
+                runtime.spawn(async move {
+                    if let Err(err) = server.notify_resource_updated(&uri).await {
+                        tracing::warn!(%err, uri, "failed to notify resource update");
+                    }
+                });
+            }),
+        )
+    }
+}
+
+/// Connections subscribed to one resource, and the watch on it
+struct UriSubscription {
+    subscribers: Vec<mpsc::UnboundedSender<Vec<u8>>>,
+    _handle: WatchHandle,
+}
+
+/// Subscribers of each resource, shared with the watch callbacks
+type Subscriptions = Arc<Mutex<HashMap<String, UriSubscription>>>;
+
+/// Tracks which connections are subscribed to each resource across servers,
+/// watching a resource only while at least one connection is subscribed and
+/// fanning each change out to all of them
+pub struct ResourceSubscriptionManager {
+    watcher: Box<dyn ResourceWatcher + Send + Sync>,
+    subscriptions: Subscriptions,
+}
+
+impl ResourceSubscriptionManager {
+    /// Constructor
+    pub fn new(watcher: impl ResourceWatcher + Send + Sync + 'static) -> Self {
+        Self {
+            watcher: Box::new(watcher),
+            subscriptions: Arc::default(),
+        }
+    }
+
+    /// Subscribes a server's connection to a resource, starting to watch it
+    /// if this is the first subscriber
+    pub fn subscribe<T: Transport>(
+        &self,
+        server: &MCPServer<T>,
+        uri: &str,
+    ) -> Result<(), McpError> {
+        let connection = server.outgoing();
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if let Some(subscription) = subscriptions.get_mut(uri) {
+            if !subscription
+                .subscribers
+                .iter()
+                .any(|subscriber| subscriber.same_channel(connection))
+            {
+                subscription.subscribers.push(connection.clone());
+            }
+            return Ok(());
+        }
+        let callback_subscriptions = Arc::clone(&self.subscriptions);
+        let owned_uri = uri.to_string();
+        let handle = self.watcher.watch(
+            uri,
+            Box::new(move || fan_out(&callback_subscriptions, &owned_uri)),
+        )?;
+        subscriptions.insert(
+            uri.into(),
+            UriSubscription {
+                subscribers: vec![connection.clone()],
+                _handle: handle,
+            },
+        );
+        Ok(())
+    }
+
+    /// Unsubscribes a server's connection from a resource, stopping the watch
+    /// if it was the last subscriber
+    pub fn unsubscribe<T: Transport>(&self, server: &MCPServer<T>, uri: &str) {
+        let connection = server.outgoing();
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let Some(subscription) = subscriptions.get_mut(uri) else {
+            return;
+        };
+        subscription
+            .subscribers
+            .retain(|subscriber| !subscriber.same_channel(connection));
+        if subscription.subscribers.is_empty() {
+            let removed = subscriptions.remove(uri);
+            // Stopping the watch may wait on a callback that needs the lock
+            drop(subscriptions);
+            drop(removed);
+        }
+    }
+
+    /// Returns the number of connections subscribed to a resource
+    pub fn subscriber_count(&self, uri: &str) -> usize {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map_or(0, |subscription| subscription.subscribers.len())
+    }
+
+    /// Notifies every subscriber that a resource has changed, as its watch
+    /// does on its own
+    pub fn notify(&self, uri: &str) {
+        fan_out(&self.subscriptions, uri)
+    }
+}
+
+/// Sends a resource update to each of its subscribers, forgetting any whose
+/// connection has closed. The watch is left running, as this may be called
+/// from its own callback.
+fn fan_out(subscriptions: &Subscriptions, uri: &str) {
+    let msg: ServerMessage = Message::Notification {
+        jsonrpc: "2.0".into(),
+        notification: ServerNotification::ResourceUpdated(
+            original::ResourceUpdatedNotificationParams { uri: uri.into() },
+        ),
+    };
+    let serialized = serde_json::to_vec(&msg).expect("notifications always serialize to JSON");
+    let mut subscriptions = subscriptions.lock().unwrap();
+    let Some(subscription) = subscriptions.get_mut(uri) else {
+        return;
+    };
+    subscription
+        .subscribers
+        .retain(|subscriber| subscriber.send(serialized.clone()).is_ok());
+}
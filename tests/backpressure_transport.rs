@@ -0,0 +1,126 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use mcp::transport::{BackpressureTransport, InMemoryTransport, Transport};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Serializes a message
+fn bytes(msg: Value) -> Vec<u8> {
+    serde_json::to_vec(&msg).unwrap()
+}
+
+/// Serializes a request with the given ID
+fn request(id: i64) -> Vec<u8> {
+    bytes(json!({ "jsonrpc": "2.0", "id": id, "method": "ping", "params": {} }))
+}
+
+/// Serializes a response to the request with the given ID
+fn response(id: i64) -> Vec<u8> {
+    bytes(json!({ "jsonrpc": "2.0", "id": id, "result": {} }))
+}
+
+/// Returns whether a message can be received right away
+async fn ready(transport: &mut BackpressureTransport<InMemoryTransport>) -> bool {
+    tokio::time::timeout(Duration::from_millis(50), transport.recv())
+        .await
+        .is_ok()
+}
+
+/// Creates a transport whose client has sent one request, already received
+/// so the limit of one is reached
+async fn at_limit() -> (BackpressureTransport<InMemoryTransport>, InMemoryTransport) {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut transport = BackpressureTransport::new(server_end, 1);
+    client.send(&request(1)).await.unwrap();
+    assert_eq!(transport.recv().await.unwrap(), request(1));
+    (transport, client)
+}
+
+#[tokio::test]
+async fn requests_wait_for_a_response_once_the_limit_is_reached() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut transport = BackpressureTransport::new(server_end, 2);
+    for id in 1..=3 {
+        client.send(&request(id)).await.unwrap();
+    }
+    assert!(ready(&mut transport).await);
+    assert!(ready(&mut transport).await);
+    assert!(!ready(&mut transport).await);
+    // Answering one request lets the next one through
+    transport.send(&response(2)).await.unwrap();
+    assert_eq!(transport.recv().await.unwrap(), request(3));
+}
+
+#[tokio::test]
+async fn held_back_request_is_not_lost() {
+    let (mut transport, mut client) = at_limit().await;
+    client.send(&request(2)).await.unwrap();
+    // Giving up on a receive while held back keeps the request
+    assert!(!ready(&mut transport).await);
+    assert_eq!(transport.peek().await.unwrap(), request(2));
+    transport.send(&response(1)).await.unwrap();
+    assert_eq!(transport.recv().await.unwrap(), request(2));
+}
+
+#[tokio::test]
+async fn notifications_and_responses_are_read_at_the_limit() {
+    let (mut transport, mut client) = at_limit().await;
+    let cancelled = bytes(json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": { "requestId": 1 },
+    }));
+    client.send(&cancelled).await.unwrap();
+    assert_eq!(transport.recv().await.unwrap(), cancelled);
+    // The client answering a request from the server
+    client.send(&response(7)).await.unwrap();
+    assert_eq!(transport.recv().await.unwrap(), response(7));
+}
+
+#[tokio::test]
+async fn only_the_matching_response_releases_a_permit() {
+    let (mut transport, mut client) = at_limit().await;
+    client.send(&request(2)).await.unwrap();
+    // Replies that answer no received request
+    let parse_error = bytes(json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": { "code": -32700, "message": "Parse error" },
+    }));
+    transport.send(&parse_error).await.unwrap();
+    transport.send(&response(5)).await.unwrap();
+    // Nor does the server's own notification or request
+    transport
+        .send(&bytes(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/list_changed",
+        })))
+        .await
+        .unwrap();
+    transport.send(&request(1)).await.unwrap();
+    assert!(!ready(&mut transport).await);
+    transport.send(&response(1)).await.unwrap();
+    assert!(ready(&mut transport).await);
+}
+
+#[tokio::test]
+async fn failed_response_still_releases_its_permit() {
+    let (mut transport, mut client) = at_limit().await;
+    client.send(&request(2)).await.unwrap();
+    drop(client);
+    assert!(transport.send(&response(1)).await.is_err());
+    assert_eq!(transport.recv().await.unwrap(), request(2));
+}
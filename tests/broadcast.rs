@@ -0,0 +1,103 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+mod common;
+
+use common::recv;
+use mcp::schema::{original, ServerNotification, VERSION};
+use mcp::server::{MCPServer, ServerCoordinator};
+use mcp::transport::{InMemoryTransport, Transport};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Starts a server sharing `coordinator`, returning it and the client end of
+/// its transport once it is running
+async fn start(
+    coordinator: &Arc<ServerCoordinator>,
+) -> (Arc<MCPServer<InMemoryTransport>>, InMemoryTransport) {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let server = Arc::new(
+        MCPServer::new(
+            server_end,
+            "test",
+            "0.1",
+            None,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .with_coordinator(coordinator.clone()),
+    );
+    let running = server.clone();
+    tokio::spawn(async move { running.run().await });
+    // Once initialize is answered, the run loop is listening for broadcasts
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        },
+    });
+    client
+        .send(&serde_json::to_vec(&initialize).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(recv(&mut client).await["id"], 1);
+    (server, client)
+}
+
+/// A notification that the resource at `uri` changed
+fn updated(uri: &str) -> ServerNotification {
+    ServerNotification::ResourceUpdated(original::ResourceUpdatedNotificationParams {
+        uri: uri.into(),
+    })
+}
+
+#[tokio::test]
+async fn notifications_reach_every_connection() {
+    let coordinator = Arc::new(ServerCoordinator::new());
+    let (first, mut first_client) = start(&coordinator).await;
+    let (_second, mut second_client) = start(&coordinator).await;
+
+    first
+        .broadcast_notification(updated("file:///a"))
+        .await
+        .unwrap();
+    for client in [&mut first_client, &mut second_client] {
+        let msg = recv(client).await;
+        assert_eq!(msg["method"], "notifications/resources/updated");
+        assert_eq!(msg["params"]["uri"], "file:///a");
+    }
+
+    coordinator
+        .broadcast_notification(updated("file:///b"))
+        .await
+        .unwrap();
+    for client in [&mut first_client, &mut second_client] {
+        assert_eq!(recv(client).await["params"]["uri"], "file:///b");
+    }
+}
+
+#[tokio::test]
+async fn broadcasting_without_connections_succeeds() {
+    let coordinator = ServerCoordinator::new();
+    assert!(coordinator
+        .broadcast_notification(updated("file:///a"))
+        .await
+        .is_ok());
+}
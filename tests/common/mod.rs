@@ -0,0 +1,82 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+// Each test crate uses only some of these helpers
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use mcp::error::McpError;
+use mcp::schema::original;
+use mcp::server::Resource;
+use mcp::transport::{InMemoryTransport, Transport};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// A resource with no contents, at the given URI
+pub struct EmptyResource(pub String);
+
+#[async_trait]
+impl Resource for EmptyResource {
+    fn uri(&self) -> &str {
+        &self.0
+    }
+
+    fn name(&self) -> &str {
+        "empty"
+    }
+
+    async fn read(&self) -> Result<Vec<original::ReadResourceResultContentsItem>, McpError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Sends a message without waiting for an answer
+pub async fn send(client: &mut InMemoryTransport, msg: Value) {
+    client
+        .send(&serde_json::to_vec(&msg).unwrap())
+        .await
+        .unwrap();
+}
+
+/// Receives the next message, failing the test if none arrives
+pub async fn recv(client: &mut InMemoryTransport) -> Value {
+    let buf = tokio::time::timeout(Duration::from_secs(1), client.recv())
+        .await
+        .expect("no message received")
+        .unwrap();
+    serde_json::from_slice(&buf).unwrap()
+}
+
+/// Sends a request and returns the response to it
+pub async fn request(
+    client: &mut InMemoryTransport,
+    id: i64,
+    method: &str,
+    params: Value,
+) -> Value {
+    send(
+        client,
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }),
+    )
+    .await;
+    let response = recv(client).await;
+    assert_eq!(response["id"], id);
+    response
+}
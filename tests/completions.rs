@@ -0,0 +1,108 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use async_trait::async_trait;
+use mcp::schema::original;
+use mcp::server::{MCPServer, Tool, ToolCallResult};
+use mcp::transport::InMemoryTransport;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const LANGUAGES: [&str; 4] = ["python", "perl", "rust", "ruby"];
+
+/// Formats code in a language, suggesting language names
+struct FormatTool;
+
+#[async_trait]
+impl Tool for FormatTool {
+    fn input_schema(&self) -> original::ToolInputSchema {
+        original::ToolInputSchema {
+            properties: HashMap::new(),
+            required: Vec::new(),
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, _arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        Ok(Vec::new())
+    }
+
+    async fn complete(&self, argument: &str, partial: &str) -> Vec<String> {
+        if argument != "language" {
+            return Vec::new();
+        }
+        LANGUAGES
+            .iter()
+            .filter(|language| language.starts_with(partial))
+            .map(|language| language.to_string())
+            .collect()
+    }
+}
+
+/// A tool without completions
+struct PlainTool;
+
+#[async_trait]
+impl Tool for PlainTool {
+    fn input_schema(&self) -> original::ToolInputSchema {
+        original::ToolInputSchema {
+            properties: HashMap::new(),
+            required: Vec::new(),
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, _arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        Ok(Vec::new())
+    }
+}
+
+fn server() -> MCPServer<InMemoryTransport> {
+    let (server_end, _client) = InMemoryTransport::pair();
+    let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+    tools.insert("format".into(), Box::new(FormatTool));
+    tools.insert("plain".into(), Box::new(PlainTool));
+    MCPServer::new(server_end, "test", "0.1", None, tools, HashMap::new())
+}
+
+#[tokio::test]
+async fn tool_arguments_are_completed_by_the_tool() {
+    let server = server();
+    assert_eq!(
+        server.tool_completions("format", "language", "r").await,
+        ["rust", "ruby"]
+    );
+    assert_eq!(
+        server.tool_completions("format", "language", "").await,
+        LANGUAGES
+    );
+}
+
+#[tokio::test]
+async fn tools_without_completions_suggest_nothing() {
+    let server = server();
+    assert!(server
+        .tool_completions("format", "style", "r")
+        .await
+        .is_empty());
+    assert!(server
+        .tool_completions("plain", "language", "r")
+        .await
+        .is_empty());
+    assert!(server
+        .tool_completions("missing", "language", "r")
+        .await
+        .is_empty());
+}
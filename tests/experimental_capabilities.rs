@@ -0,0 +1,66 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use mcp::client::{ExtraCapabilities, MCPClient};
+use mcp::server::MCPServer;
+use mcp::transport::InMemoryTransport;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Builds a JSON object from a `json!` literal
+fn object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn experimental_capabilities_are_exchanged() {
+    let (server_end, client_end) = InMemoryTransport::pair();
+    let server = Arc::new(
+        MCPServer::new(
+            server_end,
+            "test",
+            "0.1",
+            None,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .with_experimental_capability("batching", object(json!({ "max": 8 }))),
+    );
+    let running = server.clone();
+    tokio::spawn(async move { running.run().await });
+    let mut client = MCPClient::new(client_end, "test-client", "0.1")
+        .with_experimental_capability("tracing", object(json!({ "spans": true })));
+    let extra = ExtraCapabilities {
+        experimental: HashMap::from([("replay".into(), object(json!({})))]),
+        ..Default::default()
+    };
+    client.initialize(Some(extra)).await.unwrap();
+    assert_eq!(
+        client.experimental_capability("batching"),
+        Some(&json!({ "max": 8 }))
+    );
+    assert_eq!(
+        server.experimental_capability("tracing").await,
+        Some(json!({ "spans": true }))
+    );
+    assert_eq!(
+        server.experimental_capability("replay").await,
+        Some(json!({}))
+    );
+}
@@ -0,0 +1,132 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+#![cfg(feature = "http-sse")]
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use mcp::transport::{HttpSseServerTransport, Transport};
+use std::io;
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// POSTs a message to the transport's router
+async fn post(router: &Router, content_type: &str, body: &str) -> StatusCode {
+    let request = Request::post("/message")
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap().status()
+}
+
+/// Opens the event stream, returning the response carrying it
+async fn open_stream(router: &Router, accept: &str) -> axum::response::Response {
+    let request = Request::get("/sse")
+        .header(header::ACCEPT, accept)
+        .body(Body::empty())
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+/// Receives the next message, failing the test if none arrives
+async fn recv(transport: &mut HttpSseServerTransport) -> Vec<u8> {
+    tokio::time::timeout(Duration::from_secs(1), transport.recv())
+        .await
+        .expect("no message received")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn posted_messages_are_received() {
+    let mut transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    let msg = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+    assert_eq!(
+        post(&router, "application/json", msg).await,
+        StatusCode::ACCEPTED
+    );
+    assert_eq!(recv(&mut transport).await, msg.as_bytes());
+}
+
+#[tokio::test]
+async fn malformed_json_is_refused() {
+    let mut transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    assert_eq!(
+        post(&router, "application/json", "{\"jsonrpc\":").await,
+        StatusCode::BAD_REQUEST
+    );
+    // Nothing reaches the server
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), transport.recv())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn other_content_types_are_refused() {
+    let transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    assert_eq!(
+        post(&router, "text/plain", "{}").await,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    );
+}
+
+#[tokio::test]
+async fn event_stream_must_be_accepted() {
+    let transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    let response = open_stream(&router, "application/json").await;
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+}
+
+#[tokio::test]
+async fn sending_without_an_event_stream_fails() {
+    let mut transport = HttpSseServerTransport::new();
+    let err = transport.send(b"{}").await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+}
+
+#[tokio::test]
+async fn closing_the_event_stream_disconnects() {
+    let mut transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    let stream = open_stream(&router, "text/event-stream").await;
+    assert_eq!(stream.status(), StatusCode::OK);
+    transport.send(b"{}").await.unwrap();
+    drop(stream);
+    // An empty read tells the server the client is gone
+    assert!(recv(&mut transport).await.is_empty());
+    let err = transport.send(b"{}").await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+}
+
+#[tokio::test]
+async fn replaced_event_stream_does_not_disconnect() {
+    let mut transport = HttpSseServerTransport::new();
+    let router = transport.router();
+    let old = open_stream(&router, "text/event-stream").await;
+    let _new = open_stream(&router, "text/event-stream").await;
+    drop(old);
+    let msg = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+    assert_eq!(
+        post(&router, "application/json", msg).await,
+        StatusCode::ACCEPTED
+    );
+    assert_eq!(recv(&mut transport).await, msg.as_bytes());
+    transport.send(b"{}").await.unwrap();
+}
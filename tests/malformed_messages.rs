@@ -0,0 +1,78 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use mcp::error::{INVALID_REQUEST, PARSE_ERROR};
+use mcp::schema::VERSION;
+use mcp::server::MCPServer;
+use mcp::transport::{InMemoryTransport, Transport};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Sends raw bytes and returns the message sent back
+async fn exchange(client: &mut InMemoryTransport, msg: &[u8]) -> Value {
+    client.send(msg).await.unwrap();
+    serde_json::from_slice(&client.recv().await.unwrap()).unwrap()
+}
+
+/// Starts a server and returns the client end of its transport
+fn start() -> InMemoryTransport {
+    let (server_end, client) = InMemoryTransport::pair();
+    let server = MCPServer::new(
+        server_end,
+        "test",
+        "0.1",
+        None,
+        HashMap::new(),
+        HashMap::new(),
+    );
+    tokio::spawn(async move { server.run().await });
+    client
+}
+
+#[tokio::test]
+async fn invalid_json_is_answered_with_a_parse_error() {
+    let mut client = start();
+    let response = exchange(&mut client, b"{\"jsonrpc\": \"2.0\", \"id\":").await;
+    assert_eq!(response["error"]["code"], PARSE_ERROR);
+    assert!(response["id"].is_null());
+}
+
+#[tokio::test]
+async fn invalid_request_is_answered_with_its_id() {
+    let mut client = start();
+    let msg = json!({ "jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": "oops" });
+    let response = exchange(&mut client, &serde_json::to_vec(&msg).unwrap()).await;
+    assert_eq!(response["error"]["code"], INVALID_REQUEST);
+    assert_eq!(response["id"], 7);
+}
+
+#[tokio::test]
+async fn server_keeps_running_after_a_malformed_message() {
+    let mut client = start();
+    exchange(&mut client, b"not json").await;
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        },
+    });
+    let response = exchange(&mut client, &serde_json::to_vec(&initialize).unwrap()).await;
+    assert_eq!(response["id"], 1);
+    assert!(response["result"].is_object());
+}
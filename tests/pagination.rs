@@ -0,0 +1,137 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+mod common;
+
+use async_trait::async_trait;
+use common::{request, EmptyResource};
+use mcp::error::INVALID_PARAMS;
+use mcp::schema::{original, VERSION};
+use mcp::server::{MCPServer, Resource, Tool, ToolCallResult};
+use mcp::transport::InMemoryTransport;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+
+/// Does nothing
+struct NoopTool;
+
+#[async_trait]
+impl Tool for NoopTool {
+    fn input_schema(&self) -> original::ToolInputSchema {
+        original::ToolInputSchema {
+            properties: HashMap::new(),
+            required: Vec::new(),
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, _arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        Ok(Vec::new())
+    }
+}
+
+/// Returns the names of the tools on a page
+fn names(page: &Value) -> Vec<String> {
+    page["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn list_tools_pages_through_every_tool_once() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let tools: HashMap<String, Box<dyn Tool>> = (0..150)
+        .map(|i| (format!("tool_{i:03}"), Box::new(NoopTool) as Box<dyn Tool>))
+        .collect();
+    let server = MCPServer::new(server_end, "test", "0.1", None, tools, HashMap::new());
+    tokio::spawn(async move { server.run().await });
+    request(
+        &mut client,
+        0,
+        "initialize",
+        json!({
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        }),
+    )
+    .await;
+
+    let first = request(&mut client, 1, "tools/list", json!({})).await;
+    assert_eq!(names(&first).len(), 50);
+    let cursor = first["result"]["nextCursor"].clone();
+    assert!(cursor.is_string());
+
+    let second = request(&mut client, 2, "tools/list", json!({ "cursor": cursor })).await;
+    assert_eq!(names(&second).len(), 50);
+    let cursor = second["result"]["nextCursor"].clone();
+    assert!(cursor.is_string());
+
+    let third = request(&mut client, 3, "tools/list", json!({ "cursor": cursor })).await;
+    assert_eq!(names(&third).len(), 50);
+    assert!(third["result"]["nextCursor"].is_null());
+
+    let listed: Vec<String> = [first, second, third].iter().flat_map(names).collect();
+    let unique: BTreeSet<&String> = listed.iter().collect();
+    assert_eq!(unique.len(), listed.len(), "a tool was listed twice");
+    let expected: BTreeSet<String> = (0..150).map(|i| format!("tool_{i:03}")).collect();
+    assert_eq!(unique, expected.iter().collect());
+}
+
+#[tokio::test]
+async fn list_resources_pages_in_uri_order() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let resources: HashMap<String, Box<dyn Resource>> = ["c", "a", "b"]
+        .into_iter()
+        .map(|name| {
+            let uri = format!("mock://{name}");
+            (
+                uri.clone(),
+                Box::new(EmptyResource(uri)) as Box<dyn Resource>,
+            )
+        })
+        .collect();
+    let server = MCPServer::new(server_end, "test", "0.1", None, HashMap::new(), resources)
+        .with_page_size(NonZeroUsize::new(2).unwrap());
+    tokio::spawn(async move { server.run().await });
+
+    let uris = |page: &Value| -> Vec<String> {
+        page["result"]["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resource| resource["uri"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let first = request(&mut client, 1, "resources/list", json!({})).await;
+    assert_eq!(uris(&first), ["mock://a", "mock://b"]);
+    let cursor = first["result"]["nextCursor"].clone();
+    let second = request(
+        &mut client,
+        2,
+        "resources/list",
+        json!({ "cursor": cursor }),
+    )
+    .await;
+    assert_eq!(uris(&second), ["mock://c"]);
+    assert!(second["result"]["nextCursor"].is_null());
+
+    let invalid = request(&mut client, 3, "resources/list", json!({ "cursor": "99" })).await;
+    assert_eq!(invalid["error"]["code"], INVALID_PARAMS);
+}
@@ -0,0 +1,138 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use async_trait::async_trait;
+use mcp::error::{McpError, INVALID_PARAMS};
+use mcp::schema::original;
+use mcp::server::{text_message, MCPServerBuilder, Prompt, PromptArgument};
+use mcp::transport::{InMemoryTransport, Transport};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Replies with the arguments it was filled in with
+struct GreetPrompt;
+
+#[async_trait]
+impl Prompt for GreetPrompt {
+    fn name(&self) -> &str {
+        "greet"
+    }
+
+    fn arguments(&self) -> Vec<PromptArgument> {
+        vec![
+            PromptArgument {
+                name: "name".into(),
+                required: true,
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "greeting".into(),
+                required: true,
+                default: Some("Hello".into()),
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "punctuation".into(),
+                default: Some("!".into()),
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "title".into(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    async fn get(
+        &self,
+        arguments: HashMap<String, String>,
+    ) -> Result<Vec<original::PromptMessage>, McpError> {
+        let sorted: BTreeMap<_, _> = arguments.into_iter().collect();
+        Ok(vec![text_message(
+            original::Role::User,
+            serde_json::to_string(&sorted)?,
+        )])
+    }
+}
+
+/// Starts a server with the prompt, returning the client end of its transport
+fn start() -> InMemoryTransport {
+    let (server_end, client) = InMemoryTransport::pair();
+    let server = MCPServerBuilder::new()
+        .with_name("test")
+        .with_version("0.1")
+        .with_prompt("greet", GreetPrompt)
+        .build(server_end);
+    tokio::spawn(async move { server.run().await });
+    client
+}
+
+/// Gets the prompt, returning the response
+async fn get(client: &mut InMemoryTransport, arguments: Value) -> Value {
+    let msg = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "prompts/get",
+        "params": { "name": "greet", "arguments": arguments },
+    });
+    client
+        .send(&serde_json::to_vec(&msg).unwrap())
+        .await
+        .unwrap();
+    serde_json::from_slice(&client.recv().await.unwrap()).unwrap()
+}
+
+/// Returns the arguments the prompt was filled in with
+fn filled_in(response: &Value) -> Value {
+    let text = response["result"]["messages"][0]["content"]["text"]
+        .as_str()
+        .unwrap();
+    serde_json::from_str(text).unwrap()
+}
+
+#[tokio::test]
+async fn missing_arguments_take_their_default() {
+    let mut client = start();
+    let response = get(&mut client, json!({ "name": "Ada" })).await;
+    assert_eq!(
+        filled_in(&response),
+        json!({ "greeting": "Hello", "name": "Ada", "punctuation": "!" })
+    );
+}
+
+#[tokio::test]
+async fn given_arguments_replace_their_default() {
+    let mut client = start();
+    let response = get(
+        &mut client,
+        json!({ "name": "Ada", "greeting": "Hi", "title": "Dr" }),
+    )
+    .await;
+    assert_eq!(
+        filled_in(&response),
+        json!({ "greeting": "Hi", "name": "Ada", "punctuation": "!", "title": "Dr" })
+    );
+}
+
+#[tokio::test]
+async fn required_arguments_without_a_default_must_be_given() {
+    let mut client = start();
+    let response = get(&mut client, json!({ "greeting": "Hi" })).await;
+    assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    assert_eq!(
+        response["error"]["message"],
+        "Missing required arguments: name"
+    );
+}
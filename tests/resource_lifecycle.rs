@@ -0,0 +1,90 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+mod common;
+
+use common::{recv, request, EmptyResource};
+use mcp::schema::VERSION;
+use mcp::server::{MCPServer, Resource};
+use mcp::transport::InMemoryTransport;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const URI: &str = "mock://test";
+
+#[tokio::test]
+async fn subscribers_are_notified_until_they_unsubscribe() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut resources: HashMap<String, Box<dyn Resource>> = HashMap::new();
+    resources.insert(URI.into(), Box::new(EmptyResource(URI.into())));
+    let server = Arc::new(MCPServer::new(
+        server_end,
+        "test",
+        "0.1",
+        None,
+        HashMap::new(),
+        resources,
+    ));
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.run().await }
+    });
+
+    let initialize = request(
+        &mut client,
+        1,
+        "initialize",
+        json!({
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        }),
+    )
+    .await;
+    assert_eq!(
+        initialize["result"]["capabilities"]["resources"]["subscribe"],
+        true
+    );
+
+    let list = request(&mut client, 2, "resources/list", json!({})).await;
+    let listed = list["result"]["resources"].as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["uri"], URI);
+    assert_eq!(listed[0]["name"], "empty");
+
+    let subscribe = request(&mut client, 3, "resources/subscribe", json!({ "uri": URI })).await;
+    assert_eq!(subscribe["result"], json!({}));
+
+    server.notify_resource_updated(URI).await.unwrap();
+    let notification = recv(&mut client).await;
+    assert_eq!(notification["method"], "notifications/resources/updated");
+    assert_eq!(notification["params"]["uri"], URI);
+    assert!(notification.get("id").is_none());
+
+    let unsubscribe = request(
+        &mut client,
+        4,
+        "resources/unsubscribe",
+        json!({ "uri": URI }),
+    )
+    .await;
+    assert_eq!(unsubscribe["result"], json!({}));
+
+    // Nothing is queued for the update, so the ping is answered first
+    server.notify_resource_updated(URI).await.unwrap();
+    let ping = request(&mut client, 5, "ping", json!({})).await;
+    assert_eq!(ping["result"], json!({}));
+}
@@ -0,0 +1,150 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use async_trait::async_trait;
+use mcp::transport::{RetryableTransport, Transport};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// A transport whose first sends and receives fail
+#[derive(Default)]
+struct FlakyTransport {
+    /// Number of sends left to fail
+    send_failures: u32,
+    /// Number of receives left to fail
+    recv_failures: u32,
+    /// Whether reconnecting fails
+    reconnect_fails: bool,
+    /// When each send was attempted
+    send_attempts: Vec<Instant>,
+    recv_attempts: u32,
+    reconnects: u32,
+}
+
+#[async_trait]
+impl Transport for FlakyTransport {
+    async fn recv(&mut self) -> Result<Vec<u8>, io::Error> {
+        self.recv_attempts += 1;
+        if self.recv_failures > 0 {
+            self.recv_failures -= 1;
+            return Err(io::ErrorKind::ConnectionReset.into());
+        }
+        Ok(b"{}".to_vec())
+    }
+
+    async fn send(&mut self, _buf: &[u8]) -> Result<(), io::Error> {
+        self.send_attempts.push(Instant::now());
+        if self.send_failures > 0 {
+            self.send_failures -= 1;
+            // Only the first failure is a reset, to tell it apart
+            let kind = if self.send_attempts.len() == 1 {
+                io::ErrorKind::ConnectionReset
+            } else {
+                io::ErrorKind::BrokenPipe
+            };
+            return Err(kind.into());
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&mut self) -> Result<(), io::Error> {
+        self.reconnects += 1;
+        if self.reconnect_fails {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a transport, retrying quickly
+fn retrying(inner: FlakyTransport) -> RetryableTransport<FlakyTransport> {
+    RetryableTransport::new(inner).with_initial_backoff(Duration::from_millis(1))
+}
+
+#[tokio::test]
+async fn send_retries_until_it_succeeds() {
+    let mut transport = retrying(FlakyTransport {
+        send_failures: 2,
+        ..Default::default()
+    });
+    transport.send(b"{}").await.unwrap();
+    assert_eq!(transport.into_inner().send_attempts.len(), 3);
+}
+
+#[tokio::test]
+async fn send_gives_up_with_the_first_error() {
+    let mut transport = retrying(FlakyTransport {
+        send_failures: 10,
+        ..Default::default()
+    })
+    .with_max_retries(2);
+    let err = transport.send(b"{}").await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    assert_eq!(transport.into_inner().send_attempts.len(), 3);
+}
+
+#[tokio::test]
+async fn send_backoff_doubles_up_to_the_limit() {
+    let mut transport = RetryableTransport::new(FlakyTransport {
+        send_failures: 3,
+        ..Default::default()
+    })
+    .with_initial_backoff(Duration::from_millis(20))
+    .with_max_backoff(Duration::from_millis(30));
+    transport.send(b"{}").await.unwrap();
+    let attempts = transport.into_inner().send_attempts;
+    let gaps: Vec<Duration> = attempts.windows(2).map(|w| w[1] - w[0]).collect();
+    // Sleeps can run long, but never short
+    for (gap, least) in gaps.iter().zip([20, 30, 30]) {
+        assert!(*gap >= Duration::from_millis(least), "{gaps:?}");
+    }
+}
+
+#[tokio::test]
+async fn recv_reconnects_after_a_failure() {
+    let mut transport = retrying(FlakyTransport {
+        recv_failures: 2,
+        ..Default::default()
+    });
+    assert_eq!(transport.recv().await.unwrap(), b"{}");
+    let inner = transport.into_inner();
+    assert_eq!(inner.recv_attempts, 3);
+    assert_eq!(inner.reconnects, 2);
+}
+
+#[tokio::test]
+async fn recv_gives_up_when_reconnecting_fails() {
+    let mut transport = retrying(FlakyTransport {
+        recv_failures: 1,
+        reconnect_fails: true,
+        ..Default::default()
+    });
+    let err = transport.recv().await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    assert_eq!(transport.into_inner().recv_attempts, 1);
+}
+
+#[tokio::test]
+async fn recv_gives_up_after_the_last_retry() {
+    let mut transport = retrying(FlakyTransport {
+        recv_failures: 10,
+        ..Default::default()
+    })
+    .with_max_retries(2);
+    assert!(transport.recv().await.is_err());
+    let inner = transport.into_inner();
+    assert_eq!(inner.recv_attempts, 3);
+    assert_eq!(inner.reconnects, 2);
+}
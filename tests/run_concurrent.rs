@@ -0,0 +1,118 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use async_trait::async_trait;
+use mcp::schema::original;
+use mcp::server::{text_content, MCPServer, Tool, ToolCallResult};
+use mcp::transport::{InMemoryTransport, Transport};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Replies with its name after a delay
+struct Sleep {
+    name: &'static str,
+    delay: Duration,
+}
+
+#[async_trait]
+impl Tool for Sleep {
+    fn input_schema(&self) -> original::ToolInputSchema {
+        original::ToolInputSchema {
+            properties: HashMap::new(),
+            required: Vec::new(),
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, _arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        tokio::time::sleep(self.delay).await;
+        Ok(vec![text_content(self.name)])
+    }
+}
+
+fn call_tool(id: i64, name: &str) -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": name, "arguments": {} },
+    }))
+    .unwrap()
+}
+
+/// Starts a server with a slow and a fast tool, answering up to
+/// `max_concurrency` requests at once
+fn start(max_concurrency: usize) -> InMemoryTransport {
+    let (server_end, client) = InMemoryTransport::pair();
+    let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+    tools.insert(
+        "slow".into(),
+        Box::new(Sleep {
+            name: "slow",
+            delay: Duration::from_millis(500),
+        }),
+    );
+    tools.insert(
+        "fast".into(),
+        Box::new(Sleep {
+            name: "fast",
+            delay: Duration::ZERO,
+        }),
+    );
+    let server = Arc::new(MCPServer::new(
+        server_end,
+        "test",
+        "0.1",
+        None,
+        tools,
+        HashMap::new(),
+    ));
+    let max_concurrency = NonZeroUsize::new(max_concurrency).unwrap();
+    tokio::spawn(async move { server.run_concurrent(max_concurrency).await });
+    client
+}
+
+#[tokio::test]
+async fn concurrent_tool_calls_complete_independently() {
+    let mut client = start(2);
+
+    client.send(&call_tool(1, "slow")).await.unwrap();
+    client.send(&call_tool(2, "fast")).await.unwrap();
+
+    // The fast call isn't held up behind the slow one
+    let first: Value = serde_json::from_slice(&client.recv().await.unwrap()).unwrap();
+    assert_eq!(first["id"], 2);
+    assert_eq!(first["result"]["content"][0]["text"], "fast");
+    let second: Value = serde_json::from_slice(&client.recv().await.unwrap()).unwrap();
+    assert_eq!(second["id"], 1);
+    assert_eq!(second["result"]["content"][0]["text"], "slow");
+}
+
+#[tokio::test]
+async fn requests_past_the_limit_wait_their_turn() {
+    let mut client = start(1);
+
+    client.send(&call_tool(1, "slow")).await.unwrap();
+    client.send(&call_tool(2, "fast")).await.unwrap();
+
+    // Only one call runs at a time, so the fast one waits for the slow one
+    let first: Value = serde_json::from_slice(&client.recv().await.unwrap()).unwrap();
+    assert_eq!(first["id"], 1);
+    let second: Value = serde_json::from_slice(&client.recv().await.unwrap()).unwrap();
+    assert_eq!(second["id"], 2);
+}
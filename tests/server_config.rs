@@ -0,0 +1,52 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use mcp::server::{MCPServerBuilder, ServerConfig};
+use mcp::transport::InMemoryTransport;
+use serde_json::json;
+
+#[test]
+fn missing_settings_take_their_defaults() {
+    let config: ServerConfig =
+        serde_json::from_value(json!({ "name": "test", "version": "0.1" })).unwrap();
+    assert_eq!(config, ServerConfig::new("test", "0.1"));
+    assert_eq!(config.page_size.get(), 50);
+}
+
+#[test]
+fn zero_page_size_is_rejected() {
+    let config = json!({ "name": "test", "version": "0.1", "page_size": 0 });
+    assert!(serde_json::from_value::<ServerConfig>(config).is_err());
+}
+
+#[test]
+fn exported_config_loads_back() {
+    let mut config = ServerConfig::new("test", "0.1");
+    config.request_timeout_secs = Some(30);
+    config.prompts = false;
+    let exported = serde_json::to_string_pretty(&config).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ServerConfig>(&exported).unwrap(),
+        config
+    );
+}
+
+#[tokio::test]
+async fn server_builds_from_config() {
+    let (server_end, _client) = InMemoryTransport::pair();
+    let config = ServerConfig::new("test", "0.1");
+    let server = MCPServerBuilder::from_config(config).build(server_end);
+    assert_eq!(server.health().await.tools_registered, 0);
+}
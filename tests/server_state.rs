@@ -0,0 +1,173 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+mod common;
+
+use common::{recv, send, EmptyResource};
+use mcp::schema::VERSION;
+use mcp::server::{ClientCapabilityFlag, MCPServer, Resource};
+use mcp::transport::InMemoryTransport;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const URI: &str = "mock://test";
+
+/// Creates a server with one resource
+fn server(transport: InMemoryTransport) -> MCPServer<InMemoryTransport> {
+    let mut resources: HashMap<String, Box<dyn Resource>> = HashMap::new();
+    resources.insert(URI.into(), Box::new(EmptyResource(URI.into())));
+    MCPServer::new(transport, "test", "0.1", None, HashMap::new(), resources)
+}
+
+/// Initializes a server and subscribes to its resource, returning its state
+async fn saved_state() -> (Vec<u8>, Value) {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let server = Arc::new(server(server_end));
+    tokio::spawn({
+        let server = server.clone();
+        async move { server.run().await }
+    });
+    send(
+        &mut client,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": VERSION,
+                "capabilities": { "roots": { "listChanged": true } },
+                "clientInfo": { "name": "test-client", "version": "0.1" },
+            },
+        }),
+    )
+    .await;
+    recv(&mut client).await;
+    send(
+        &mut client,
+        json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    )
+    .await;
+    send(
+        &mut client,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "resources/subscribe",
+            "params": { "uri": URI },
+        }),
+    )
+    .await;
+    assert_eq!(recv(&mut client).await["id"], 2);
+    let state = server.serialize_state().await.unwrap();
+    (state, server.inspect().await)
+}
+
+#[tokio::test]
+async fn restored_server_remembers_client_and_subscriptions() {
+    let (state, before) = saved_state().await;
+    let (server_end, _client) = InMemoryTransport::pair();
+    let mut restored = server(server_end);
+    restored.restore_state(&state).unwrap();
+    let after = restored.inspect().await;
+    assert_eq!(after["client_state"], before["client_state"]);
+    assert_eq!(after["client_state"]["initialized"], true);
+    assert_eq!(after["client_state"]["clientInfo"]["name"], "test-client");
+    assert_eq!(restored.subscribed_uris().await, [URI]);
+    assert!(restored.has_capability(ClientCapabilityFlag::Roots).await);
+    assert!(
+        !restored
+            .has_capability(ClientCapabilityFlag::Sampling)
+            .await
+    );
+}
+
+#[tokio::test]
+async fn restored_server_serves_without_a_new_handshake() {
+    let (state, _) = saved_state().await;
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut restored = server(server_end).with_handshake_timeout(Duration::from_millis(10));
+    restored.restore_state(&state).unwrap();
+    let restored = Arc::new(restored);
+    tokio::spawn({
+        let restored = restored.clone();
+        async move { restored.run().await }
+    });
+    // Long past the handshake timeout, the connection is still served
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    send(
+        &mut client,
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "ping", "params": {} }),
+    )
+    .await;
+    assert_eq!(recv(&mut client).await["id"], 1);
+    // And the restored subscription is still notified
+    restored.notify_resource_updated(URI).await.unwrap();
+    let notification = recv(&mut client).await;
+    assert_eq!(notification["method"], "notifications/resources/updated");
+    assert_eq!(notification["params"]["uri"], URI);
+}
+
+#[tokio::test]
+async fn fresh_state_round_trips() {
+    let (server_end, _client) = InMemoryTransport::pair();
+    let state = server(server_end).serialize_state().await.unwrap();
+    let (server_end, _client) = InMemoryTransport::pair();
+    let mut restored = server(server_end);
+    restored.restore_state(&state).unwrap();
+    assert!(restored.inspect().await["client_state"].is_null());
+    assert!(restored.subscribed_uris().await.is_empty());
+}
+
+#[tokio::test]
+async fn corrupt_state_is_an_error() {
+    let (server_end, _client) = InMemoryTransport::pair();
+    assert!(server(server_end).restore_state(&[0xff; 4]).is_err());
+}
+
+/// Watches nothing, leaving changes to be reported by hand
+#[cfg(feature = "watch")]
+struct ManualWatcher;
+
+#[cfg(feature = "watch")]
+impl mcp::watch::ResourceWatcher for ManualWatcher {
+    fn watch(
+        &self,
+        _uri: &str,
+        _callback: Box<dyn Fn() + Send + 'static>,
+    ) -> Result<mcp::watch::WatchHandle, mcp::error::McpError> {
+        Ok(mcp::watch::WatchHandle::new(()))
+    }
+}
+
+#[cfg(feature = "watch")]
+#[tokio::test]
+async fn restored_subscriptions_are_watched() {
+    use mcp::watch::ResourceSubscriptionManager;
+
+    let (state, _) = saved_state().await;
+    let manager = Arc::new(ResourceSubscriptionManager::new(ManualWatcher));
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut restored = server(server_end).with_subscription_manager(manager.clone());
+    restored.restore_state(&state).unwrap();
+    assert_eq!(manager.subscriber_count(URI), 1);
+    tokio::spawn(async move { restored.run().await });
+
+    manager.notify(URI);
+    let notification = recv(&mut client).await;
+    assert_eq!(notification["method"], "notifications/resources/updated");
+    assert_eq!(notification["params"]["uri"], URI);
+}
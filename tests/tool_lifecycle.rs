@@ -0,0 +1,244 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+mod common;
+
+use async_trait::async_trait;
+use common::request;
+use mcp::error::INVALID_PARAMS;
+use mcp::schema::{original, VERSION};
+use mcp::server::{text_content, MCPServer, Tool, ToolCallResponse, ToolCallResult, ToolRegistry};
+use mcp::transport::InMemoryTransport;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Replies with the text it is given
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    fn description(&self) -> Option<&str> {
+        Some("Echoes its input")
+    }
+
+    fn input_schema(&self) -> original::ToolInputSchema {
+        let mut text = serde_json::Map::new();
+        text.insert("type".into(), "string".into());
+        original::ToolInputSchema {
+            properties: HashMap::from([("text".into(), text)]),
+            required: vec!["text".into()],
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        let text = arguments.get("text").and_then(Value::as_str).unwrap_or("");
+        Ok(vec![text_content(text)])
+    }
+}
+
+/// Describes a number, using the response shorthands
+struct DescribeTool;
+
+#[async_trait]
+impl Tool for DescribeTool {
+    fn input_schema(&self) -> original::ToolInputSchema {
+        original::ToolInputSchema {
+            properties: HashMap::new(),
+            required: Vec::new(),
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Map<String, Value>) -> ToolCallResult {
+        let Some(n) = arguments.get("n").and_then(Value::as_i64) else {
+            return ToolCallResponse::error("n must be an integer");
+        };
+        ToolCallResponse::builder()
+            .with_text(&n.to_string())
+            .with_json(&json!({ "even": n % 2 == 0 }))
+            .build()
+    }
+}
+
+#[tokio::test]
+async fn tools_are_advertised_listed_and_called() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+    tools.insert("echo".into(), Box::new(EchoTool));
+    let server = MCPServer::new(server_end, "test", "0.1", None, tools, HashMap::new());
+    tokio::spawn(async move { server.run().await });
+
+    let initialize = request(
+        &mut client,
+        1,
+        "initialize",
+        json!({
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        }),
+    )
+    .await;
+    assert!(initialize["result"]["capabilities"]["tools"].is_object());
+
+    let list = request(&mut client, 2, "tools/list", json!({})).await;
+    let listed = list["result"]["tools"].as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["name"], "echo");
+    assert_eq!(listed[0]["description"], "Echoes its input");
+    assert_eq!(listed[0]["inputSchema"]["required"], json!(["text"]));
+
+    let call = request(
+        &mut client,
+        3,
+        "tools/call",
+        json!({ "name": "echo", "arguments": { "text": "hello" } }),
+    )
+    .await;
+    assert_eq!(call["result"]["content"][0]["text"], "hello");
+    assert_ne!(call["result"]["isError"], true);
+
+    let unknown = request(
+        &mut client,
+        4,
+        "tools/call",
+        json!({ "name": "missing", "arguments": {} }),
+    )
+    .await;
+    assert!(unknown.get("result").is_none());
+    assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+    assert_eq!(unknown["error"]["message"], "Unknown tool: missing");
+    assert_eq!(
+        unknown["error"]["data"],
+        json!({ "toolNotFound": { "name": "missing" } })
+    );
+}
+
+#[tokio::test]
+async fn tools_can_return_built_responses() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let mut tools: HashMap<String, Box<dyn Tool>> = HashMap::new();
+    tools.insert("describe".into(), Box::new(DescribeTool));
+    let server = MCPServer::new(server_end, "test", "0.1", None, tools, HashMap::new());
+    tokio::spawn(async move { server.run().await });
+
+    let call = request(
+        &mut client,
+        1,
+        "tools/call",
+        json!({ "name": "describe", "arguments": { "n": 4 } }),
+    )
+    .await;
+    assert_eq!(call["result"]["content"][0]["text"], "4");
+    assert_eq!(call["result"]["content"][1]["text"], r#"{"even":true}"#);
+    assert_ne!(call["result"]["isError"], true);
+
+    let failed = request(
+        &mut client,
+        2,
+        "tools/call",
+        json!({ "name": "describe", "arguments": { "n": "four" } }),
+    )
+    .await;
+    assert_eq!(
+        failed["result"]["content"][0]["text"],
+        "n must be an integer"
+    );
+    assert_eq!(failed["result"]["isError"], true);
+}
+
+#[tokio::test]
+async fn tools_added_to_a_shared_registry_reach_every_server() {
+    let registry = Arc::new(ToolRegistry::new(HashMap::new()));
+    let (first_end, _first_client) = InMemoryTransport::pair();
+    let (second_end, mut second_client) = InMemoryTransport::pair();
+    let first = MCPServer::new_with_registry(
+        first_end,
+        "test",
+        "0.1",
+        None,
+        Arc::clone(&registry),
+        Arc::default(),
+        Arc::default(),
+    );
+    let second = MCPServer::new_with_registry(
+        second_end,
+        "test",
+        "0.1",
+        None,
+        registry,
+        Arc::default(),
+        Arc::default(),
+    );
+    tokio::spawn(async move { second.run().await });
+
+    first
+        .extend_tools([
+            ("echo".to_string(), Box::new(EchoTool) as Box<dyn Tool>),
+            ("describe".to_string(), Box::new(DescribeTool)),
+        ])
+        .await;
+
+    let list = request(&mut second_client, 1, "tools/list", json!({})).await;
+    let mut names = list["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["describe", "echo"]);
+}
+
+#[tokio::test]
+async fn capabilities_from_registry_reflect_tools_added_later() {
+    let (server_end, mut client) = InMemoryTransport::pair();
+    let server = Arc::new(
+        MCPServer::new(
+            server_end,
+            "test",
+            "0.1",
+            None,
+            HashMap::new(),
+            HashMap::new(),
+        )
+        .with_capabilities_from_registry(),
+    );
+    server
+        .extend_tools([("echo".to_string(), Box::new(EchoTool) as Box<dyn Tool>)])
+        .await;
+    tokio::spawn({
+        let server = server.clone();
+        async move { server.run().await }
+    });
+
+    let initialize = request(
+        &mut client,
+        1,
+        "initialize",
+        json!({
+            "protocolVersion": VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "0.1" },
+        }),
+    )
+    .await;
+    let capabilities = &initialize["result"]["capabilities"];
+    assert!(capabilities["tools"].is_object());
+    assert!(capabilities.get("resources").is_none());
+    assert!(capabilities.get("prompts").is_none());
+}
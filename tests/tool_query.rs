@@ -0,0 +1,255 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+#![allow(dead_code)]
+use mcp::server::{Tool, ToolCallResult};
+use serde::Deserialize;
+use serde_json::{json, Map, Number, Value};
+use tool_macros::{tool_handler, McpTool, ToolQuery};
+
+/// Paging options, flattened into other queries
+#[derive(ToolQuery, Deserialize)]
+struct Paging {
+    /// Page number
+    page: u32,
+    limit: Option<u32>,
+}
+
+#[derive(ToolQuery, Deserialize)]
+enum Order {
+    #[serde(rename = "asc")]
+    Ascending,
+    #[serde(rename = "desc")]
+    Descending,
+}
+
+#[derive(ToolQuery, Deserialize)]
+struct Search {
+    /// The search text
+    #[schema(min_length = 1, max_length = 100)]
+    text: String,
+    #[schema(enum_values = ["en", "fr"])]
+    lang: Option<String>,
+    #[schema(enum_values = "Order")]
+    order: Option<String>,
+    #[serde(default)]
+    exact: bool,
+    #[schema(const = "v1")]
+    version: String,
+    #[serde(flatten)]
+    paging: Paging,
+}
+
+#[derive(ToolQuery, Deserialize)]
+struct Legacy {
+    #[deprecated]
+    old: Option<u32>,
+    /// Old page size
+    #[schema(deprecated_reason = "use limit")]
+    size: Option<u32>,
+}
+
+/// Returns the schema of a property as JSON
+fn property(schema: &serde_json::Map<String, Value>, name: &str) -> Value {
+    schema["properties"][name].clone()
+}
+
+/// Returns the required fields of a schema, sorted
+fn required(schema: &serde_json::Map<String, Value>) -> Vec<String> {
+    let mut required: Vec<String> = serde_json::from_value(schema["required"].clone()).unwrap();
+    required.sort();
+    required
+}
+
+#[test]
+fn fields_map_to_json_types() {
+    let schema = Search::json_schema();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(property(&schema, "text")["type"], "string");
+    assert_eq!(property(&schema, "text")["description"], "The search text");
+    assert_eq!(property(&schema, "exact")["type"], "boolean");
+    assert_eq!(property(&schema, "page")["type"], "number");
+}
+
+#[test]
+fn optional_and_defaulted_fields_are_not_required() {
+    assert_eq!(
+        required(&Search::json_schema()),
+        ["page", "text", "version"]
+    );
+}
+
+#[test]
+fn const_fields_stay_required() {
+    let schema = Search::json_schema();
+    assert_eq!(property(&schema, "version")["const"], "v1");
+    assert!(required(&schema).contains(&"version".to_string()));
+    // Input leaving it out can't be deserialized, so must not be accepted
+    let input = json!({ "text": "a", "page": 1 });
+    assert!(serde_json::from_value::<Search>(input).is_err());
+}
+
+#[test]
+fn string_lengths_are_listed() {
+    let text = property(&Search::json_schema(), "text");
+    assert_eq!(text["minLength"], 1);
+    assert_eq!(text["maxLength"], 100);
+}
+
+#[test]
+fn enum_values_are_listed() {
+    let schema = Search::json_schema();
+    assert_eq!(property(&schema, "lang")["enum"], json!(["en", "fr"]));
+    assert_eq!(property(&schema, "order")["enum"], json!(["asc", "desc"]));
+    assert_eq!(Order::enum_values(), ["asc", "desc"]);
+    assert!(matches!("desc".parse(), Ok(Order::Descending)));
+    assert!("sideways".parse::<Order>().is_err());
+}
+
+#[test]
+fn flattened_fields_are_merged() {
+    let schema = Search::json_schema();
+    assert_eq!(property(&schema, "page")["description"], "Page number");
+    assert!(schema["properties"].get("paging").is_none());
+}
+
+#[test]
+fn deprecated_fields_are_described() {
+    let schema = Legacy::json_schema();
+    assert_eq!(property(&schema, "old")["description"], "(DEPRECATED)");
+    assert_eq!(
+        property(&schema, "size")["description"],
+        "Old page size (DEPRECATED: use limit)"
+    );
+}
+
+#[test]
+fn to_tool_matches_the_schema() {
+    let tool = Search::to_tool("search", "Searches");
+    assert_eq!(tool.name, "search");
+    assert_eq!(tool.description.as_deref(), Some("Searches"));
+    assert_eq!(tool.input_schema.type_, "object");
+    let mut required = tool.input_schema.required.clone();
+    required.sort();
+    assert_eq!(required, ["page", "text", "version"]);
+    assert_eq!(
+        tool.input_schema.properties.len(),
+        Search::generate_schema().len()
+    );
+}
+
+/// A type from elsewhere, without a `ToolQuery` schema
+#[derive(Deserialize)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+/// A type with a schema registered at runtime
+#[derive(Deserialize)]
+struct Date(String);
+
+/// A type serialized as a string
+#[derive(Deserialize)]
+struct Timestamp(String);
+
+#[derive(ToolQuery, Deserialize)]
+struct Place {
+    point: Point,
+    when: Option<Date>,
+    #[schema(type = "string")]
+    seen: Timestamp,
+}
+
+#[test]
+fn unknown_types_are_objects() {
+    assert_eq!(property(&Place::json_schema(), "point")["type"], "object");
+}
+
+#[test]
+fn given_type_replaces_unknown_type() {
+    assert_eq!(property(&Place::json_schema(), "seen")["type"], "string");
+}
+
+#[test]
+fn registered_schema_replaces_unknown_type() {
+    mcp::tool_schema::ToolSchemaRegistry::register::<Date>(
+        json!({ "type": "string", "format": "date" }),
+    );
+    let when = property(&Place::json_schema(), "when");
+    assert_eq!(when["type"], "string");
+    assert_eq!(when["format"], "date");
+}
+
+#[derive(ToolQuery, Deserialize)]
+struct Tagged {
+    name: String,
+    #[serde(flatten)]
+    tags: std::collections::HashMap<String, String>,
+}
+
+#[test]
+fn flattened_maps_allow_other_properties() {
+    let schema = Tagged::json_schema();
+    assert!(schema["properties"].get("tags").is_none());
+    assert_eq!(schema["additionalProperties"], true);
+    assert_eq!(required(&schema), ["name"]);
+    // Only schemas with such a field mention them
+    assert!(Search::json_schema().get("additionalProperties").is_none());
+}
+
+#[derive(ToolQuery, Deserialize)]
+struct Measurement {
+    imported: Number,
+    qualified: serde_json::Number,
+    extra: Map<String, Value>,
+}
+
+#[test]
+fn serde_json_types_match_however_they_are_named() {
+    let schema = Measurement::json_schema();
+    assert_eq!(property(&schema, "imported")["type"], "number");
+    assert_eq!(property(&schema, "qualified")["type"], "number");
+    assert_eq!(property(&schema, "extra")["type"], "object");
+}
+
+/// Looks up a record
+#[derive(McpTool, Deserialize)]
+#[schema(deprecated)]
+struct Lookup {
+    id: u32,
+}
+
+impl Lookup {
+    #[tool_handler]
+    async fn call(_args: Self) -> ToolCallResult {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn deprecated_structs_describe_deprecated_tools() {
+    assert_eq!(Lookup::json_schema()["deprecated"], true);
+    assert_eq!(
+        Lookup::to_tool("lookup", "Looks up a record")
+            .description
+            .as_deref(),
+        Some("Looks up a record (DEPRECATED)")
+    );
+    assert_eq!(
+        LookupTool.description(),
+        Some("Looks up a record (DEPRECATED)")
+    );
+}
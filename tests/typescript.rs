@@ -0,0 +1,203 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+use async_trait::async_trait;
+use mcp::error::McpError;
+use mcp::schema::original;
+use mcp::server::{
+    Prompt, PromptArgument, PromptRegistry, Resource, ResourceRegistry, Tool, ToolCallResult,
+    ToolRegistry,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Looks up the weather
+struct WeatherTool;
+
+#[async_trait]
+impl Tool for WeatherTool {
+    fn description(&self) -> Option<&str> {
+        Some("Gets the forecast")
+    }
+
+    fn input_schema(&self) -> original::ToolInputSchema {
+        let schema =
+            |value: Value| -> Map<String, Value> { serde_json::from_value(value).unwrap() };
+        original::ToolInputSchema {
+            properties: HashMap::from([
+                (
+                    "city".into(),
+                    schema(json!({ "type": "string", "description": "City name" })),
+                ),
+                (
+                    "units".into(),
+                    schema(json!({ "type": "string", "enum": ["c", "f"] })),
+                ),
+                (
+                    "days".into(),
+                    schema(json!({ "type": ["integer", "null"] })),
+                ),
+                (
+                    "tags".into(),
+                    schema(json!({ "type": "array", "items": { "type": "string" } })),
+                ),
+                ("max-age".into(), schema(json!({ "type": "number" }))),
+            ]),
+            required: vec!["city".into()],
+            type_: "object".into(),
+        }
+    }
+
+    async fn call(&self, _arguments: Map<String, Value>) -> ToolCallResult {
+        Ok(Vec::new())
+    }
+}
+
+/// A configuration file
+struct ConfigResource;
+
+#[async_trait]
+impl Resource for ConfigResource {
+    fn uri(&self) -> &str {
+        "file:///config.toml"
+    }
+
+    fn name(&self) -> &str {
+        "app config"
+    }
+
+    async fn read(&self) -> Result<Vec<original::ReadResourceResultContentsItem>, McpError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Asks for a summary
+struct SummarizePrompt;
+
+#[async_trait]
+impl Prompt for SummarizePrompt {
+    fn name(&self) -> &str {
+        "summarize"
+    }
+
+    fn arguments(&self) -> Vec<PromptArgument> {
+        vec![
+            PromptArgument {
+                name: "text".into(),
+                required: true,
+                ..Default::default()
+            },
+            PromptArgument {
+                name: "style".into(),
+                description: Some("Tone of the summary".into()),
+                required: true,
+                default: Some("brief".into()),
+            },
+        ]
+    }
+
+    async fn get(
+        &self,
+        _arguments: HashMap<String, String>,
+    ) -> Result<Vec<original::PromptMessage>, McpError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Renders bindings for one tool, resource and prompt
+async fn bindings() -> String {
+    let tools = ToolRegistry::new(HashMap::from([(
+        "get_weather".to_string(),
+        Box::new(WeatherTool) as Box<dyn Tool>,
+    )]));
+    let resources = ResourceRegistry::new(HashMap::from([(
+        "file:///config.toml".to_string(),
+        Box::new(ConfigResource) as Box<dyn Resource>,
+    )]));
+    let prompts = PromptRegistry::new(HashMap::from([(
+        "summarize".to_string(),
+        Box::new(SummarizePrompt) as Box<dyn Prompt>,
+    )]));
+    mcp::typescript::bindings(&tools, &resources, &prompts).await
+}
+
+#[tokio::test]
+async fn tool_arguments_become_an_interface() {
+    let bindings = bindings().await;
+    let expected = r#"export interface GetWeatherArguments {
+  /** City name */
+  city: string;
+  days?: number | null;
+  "max-age"?: number;
+  tags?: Array<string>;
+  units?: "c" | "f";
+}
+
+/** Gets the forecast */
+export declare function getWeather(args: GetWeatherArguments): Promise<ContentItem[]>;
+"#;
+    assert!(bindings.contains(expected), "{bindings}");
+}
+
+#[tokio::test]
+async fn resources_become_read_functions() {
+    assert!(bindings()
+        .await
+        .contains("export declare function readAppConfig(): Promise<ResourceContents[]>;"));
+}
+
+#[tokio::test]
+async fn prompt_arguments_with_defaults_are_optional() {
+    let bindings = bindings().await;
+    assert!(bindings.contains("  text: string;\n"));
+    assert!(bindings.contains("  /** Tone of the summary */\n  style?: string;\n"));
+    assert!(bindings.contains(
+        "export declare function getSummarize(args: SummarizePromptArguments): Promise<PromptMessage[]>;"
+    ));
+}
+
+#[tokio::test]
+async fn output_content_is_a_union() {
+    assert!(bindings()
+        .await
+        .contains("export type ContentItem = TextContent | ImageContent | EmbeddedResource;"));
+}
+
+#[tokio::test]
+async fn clashing_names_are_numbered() {
+    let tools = ToolRegistry::new(
+        ["get_weather", "get-weather", "getWeather", "delete", "天気"]
+            .into_iter()
+            .map(|name| (name.to_string(), Box::new(WeatherTool) as Box<dyn Tool>))
+            .collect(),
+    );
+    let prompts = PromptRegistry::new(HashMap::from([(
+        "weather".to_string(),
+        Box::new(SummarizePrompt) as Box<dyn Prompt>,
+    )]));
+    let bindings =
+        mcp::typescript::bindings(&tools, &ResourceRegistry::new(HashMap::new()), &prompts).await;
+    for declaration in [
+        "function getWeather(args: GetWeatherArguments)",
+        "function getWeather2(args: GetWeather2Arguments)",
+        "function getWeather3(args: GetWeather3Arguments)",
+        "function delete2(args: Delete2Arguments)",
+        "function 天気(args: 天気Arguments)",
+        // The prompt's function would otherwise be the first tool's
+        "function getWeather4(args: Weather4PromptArguments)",
+    ] {
+        assert!(bindings.contains(declaration), "{declaration}\n{bindings}");
+    }
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use tool_macros::ToolQuery;
+
+#[derive(ToolQuery, Deserialize)]
+struct Query {
+    #[schema(type = "text")]
+    text: String,
+}
+
+fn main() {}
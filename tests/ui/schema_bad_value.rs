@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use tool_macros::ToolQuery;
+
+#[derive(ToolQuery, Deserialize)]
+struct Query {
+    #[schema(min_length = "three")]
+    text: String,
+}
+
+fn main() {}
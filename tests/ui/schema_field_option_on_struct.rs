@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use tool_macros::ToolQuery;
+
+#[derive(ToolQuery, Deserialize)]
+#[schema(max_length = 3)]
+struct Query {
+    text: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use tool_macros::ToolQuery;
+
+#[derive(ToolQuery, Deserialize)]
+#[schema(depreciated)]
+struct Query {
+    text: String,
+}
+
+fn main() {}
@@ -0,0 +1,99 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+//! The generated types don't implement `Yokeable` themselves, so these tests
+//! wrap the borrowing `zerocopy` variants in local types deriving it. The
+//! derive only compiles for types covariant in their lifetime, and attaching
+//! them to a buffer checks that what they borrow outlives the parse.
+use mcp::schema::original::zerocopy::{CallToolResult, InitializeResult, ListToolsResult};
+use serde_json::json;
+use yoke::{Yoke, Yokeable};
+
+#[derive(Yokeable)]
+struct YokedInitialize<'a>(InitializeResult<'a>);
+
+#[derive(Yokeable)]
+struct YokedCallTool<'a>(CallToolResult<'a>);
+
+#[derive(Yokeable)]
+struct YokedListTools<'a>(ListToolsResult<'a>);
+
+/// Serializes JSON into an owned buffer to borrow from
+fn cart(value: serde_json::Value) -> Vec<u8> {
+    serde_json::to_vec(&value).unwrap()
+}
+
+#[test]
+fn initialize_result_borrows_from_its_buffer() {
+    let buf = cart(json!({
+        "capabilities": { "tools": { "listChanged": true } },
+        "instructions": "Be nice",
+        "protocolVersion": "2024-11-05",
+        "serverInfo": { "name": "test", "version": "0.1" },
+    }));
+    let yoke: Yoke<YokedInitialize<'static>, Vec<u8>> = Yoke::attach_to_cart(buf, |buf| {
+        YokedInitialize(serde_json::from_slice(buf).unwrap())
+    });
+    let result = &yoke.get().0;
+    assert_eq!(result.instructions, Some("Be nice"));
+    assert_eq!(result.protocol_version, "2024-11-05");
+    assert_eq!(result.server_info.name, "test");
+    assert_eq!(
+        result
+            .capabilities
+            .tools
+            .as_ref()
+            .and_then(|tools| tools.list_changed),
+        Some(true)
+    );
+}
+
+#[test]
+fn call_tool_result_borrows_from_its_buffer() {
+    let buf = cart(json!({
+        "content": [{ "type": "text", "text": "hello" }],
+        "isError": false,
+    }));
+    let yoke: Yoke<YokedCallTool<'static>, Vec<u8>> = Yoke::attach_to_cart(buf, |buf| {
+        YokedCallTool(serde_json::from_slice(buf).unwrap())
+    });
+    let result = &yoke.get().0;
+    assert_eq!(result.content.len(), 1);
+    assert_eq!(result.is_error, Some(false));
+    // Re-serializing reads the borrowed text back out of the buffer
+    assert_eq!(
+        serde_json::to_value(result).unwrap()["content"][0]["text"],
+        "hello"
+    );
+}
+
+#[test]
+fn list_tools_result_borrows_from_its_buffer() {
+    let buf = cart(json!({
+        "tools": [{
+            "name": "echo",
+            "description": "Echoes its input",
+            "inputSchema": { "type": "object", "properties": {}, "required": [] },
+        }],
+    }));
+    let yoke: Yoke<YokedListTools<'static>, Vec<u8>> = Yoke::attach_to_cart(buf, |buf| {
+        YokedListTools(serde_json::from_slice(buf).unwrap())
+    });
+    let result = &yoke.get().0;
+    assert_eq!(result.tools.len(), 1);
+    assert_eq!(result.tools[0].name, "echo");
+    assert_eq!(result.tools[0].description, Some("Echoes its input"));
+    assert!(result.next_cursor.is_none());
+}
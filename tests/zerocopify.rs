@@ -0,0 +1,115 @@
+// Rust MCP
+// Copyright (C) 2025 Jade Harley
+//
+// This program is free software: you can redistribute it and/or modify it
+// under the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// This program is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of  MERCHANTABILITY or
+// FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public License for
+// more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// this program.  If not, see <http://www.gnu.org/licenses/>.
+#[path = "../build/zerocopify.rs"]
+mod zerocopify;
+
+use quote::ToTokens;
+use syn::{parse_quote, File, Item};
+
+/// Runs the transform and returns the items of the generated zerocopy module
+fn zerocopy_items(mut ast: File) -> Vec<Item> {
+    zerocopify::transform_ast(&mut ast, &[]);
+    let Some(Item::Mod(module)) = ast.items.pop() else {
+        panic!("transform_ast did not append a module");
+    };
+    assert_eq!(module.ident, "zerocopy");
+    module.content.expect("zerocopy module has no items").1
+}
+
+/// Finds a generated struct or enum by name, as a token string
+fn item(items: &[Item], name: &str) -> String {
+    items
+        .iter()
+        .find(|item| match item {
+            Item::Struct(item) => item.ident == name,
+            Item::Enum(item) => item.ident == name,
+            _ => false,
+        })
+        .unwrap_or_else(|| panic!("{name} not generated"))
+        .to_token_stream()
+        .to_string()
+}
+
+/// Normalizes token spacing so expectations can be written as Rust
+fn tokens(item: Item) -> String {
+    item.to_token_stream().to_string()
+}
+
+#[test]
+fn logging_message_borrows_optional_strings() {
+    let items = zerocopy_items(parse_quote! {
+        #[derive(Clone, Debug, Deserialize, Serialize)]
+        pub struct LoggingMessageNotificationParams {
+            pub data: serde_json::Value,
+            pub level: LoggingLevel,
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub logger: Option<String>,
+        }
+
+        #[derive(Clone, Debug, Deserialize, Serialize)]
+        pub enum ServerNotification {
+            LoggingMessage(LoggingMessageNotificationParams),
+            Logger(Option<String>),
+        }
+    });
+
+    assert_eq!(
+        item(&items, "LoggingMessageNotificationParams"),
+        tokens(parse_quote! {
+            #[derive(Clone, Debug, Deserialize, Serialize)]
+            pub struct LoggingMessageNotificationParams<'a> {
+                pub data: serde_json::Value,
+                pub level: LoggingLevel,
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                #[serde(borrow)]
+                pub logger: Option<&'a str>,
+            }
+        })
+    );
+    assert_eq!(
+        item(&items, "ServerNotification"),
+        tokens(parse_quote! {
+            #[derive(Clone, Debug, Deserialize, Serialize)]
+            pub enum ServerNotification<'a> {
+                LoggingMessage(#[serde(borrow)] LoggingMessageNotificationParams<'a>),
+                Logger(#[serde(borrow)] Option<&'a str>),
+            }
+        })
+    );
+}
+
+#[test]
+fn map_keys_stay_owned() {
+    let items = zerocopy_items(parse_quote! {
+        #[derive(Clone, Debug, Deserialize, Serialize)]
+        pub struct Capabilities {
+            pub experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+            pub names: BTreeMap<String, String>,
+        }
+    });
+
+    assert_eq!(
+        item(&items, "Capabilities"),
+        tokens(parse_quote! {
+            #[derive(Clone, Debug, Deserialize, Serialize)]
+            pub struct Capabilities<'a> {
+                pub experimental: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+                #[serde(borrow)]
+                pub names: BTreeMap<String, &'a str>,
+            }
+        })
+    );
+}
@@ -1,11 +1,38 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Meta};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    parse::Parser, parse_macro_input, punctuated::Punctuated, spanned::Spanned, Attribute, Data,
+    DeriveInput, Fields, Meta, Token,
+};
 
 /// A derive macro that generates a method to convert a struct into a JSON Schema-like
 /// representation. The outer structure is a HashMap, while the inner structure uses
 /// serde_json::Map for compatibility with JSON values.
-#[proc_macro_derive(ToolQuery)]
+///
+/// Fields marked `#[schema(const = "...")]` always hold the given value.
+/// They are still required, since the macro can't fill them in when
+/// deserializing.
+///
+/// Fields marked `#[deprecated]` or `#[schema(deprecated_reason = "...")]`
+/// have `(DEPRECATED)` appended to their description. Marking the struct
+/// itself `#[schema(deprecated)]` adds `"deprecated": true` to its schema and
+/// appends `(DEPRECATED)` to the description of the tool built from it.
+///
+/// String fields accept `#[schema(min_length = N, max_length = M)]`.
+///
+/// Fields whose type has no known JSON Schema type are described by the
+/// schema registered for the type with `ToolSchemaRegistry`, if any, or else
+/// as objects. `#[schema(type = "string")]` gives the type to use instead.
+/// The `strict_schema` feature rejects such fields unless they give a type,
+/// and also requires every field type to implement `Serialize`.
+///
+/// Deriving it on an enum of unit variants generates `enum_values()`, a
+/// `FromStr` impl, and `deserialize_from_str` for use with
+/// `#[serde(deserialize_with = "...")]`. Fields of such an enum should be
+/// marked `#[schema(enum_values = "MyEnum")]` to list its values in the schema.
+/// A string field can instead list its values directly, as in
+/// `#[schema(enum_values = ["asc", "desc"])]`.
+#[proc_macro_derive(ToolQuery, attributes(schema))]
 pub fn schema_gen(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
@@ -14,16 +41,195 @@ pub fn schema_gen(input: TokenStream) -> TokenStream {
     // Extract fields from struct
     let fields = match input.data {
         Data::Struct(ref data) => match data.fields {
-            Fields::Named(ref fields) => &fields.named,
+            Fields::Named(ref fields) if !fields.named.is_empty() => &fields.named,
+            // An empty schema would silently accept no arguments at all
+            Fields::Named(_) | Fields::Unit => {
+                return syn::Error::new_spanned(&name, "ToolQuery requires at least one named field")
+                    .to_compile_error()
+                    .into();
+            }
             _ => panic!("SchemaGen only supports structs with named fields"),
         },
-        _ => panic!("SchemaGen only supports structs"),
+        Data::Enum(ref data) => return enum_schema_gen(&name, data),
+        _ => panic!("SchemaGen only supports structs and enums"),
+    };
+
+    // Calls on a flattened type resolve to its inherent `__tool_query_flatten`
+    // if it derives `ToolQuery`, and to this blanket fallback otherwise
+    let flatten_fallback = quote! {
+        #[allow(dead_code)]
+        trait __ToolQueryFlattenFallback {
+            fn __tool_query_flatten() -> Option<(
+                std::collections::HashMap<String, serde_json::Map<String, serde_json::Value>>,
+                Vec<String>,
+            )> {
+                None
+            }
+        }
+        impl<T: ?Sized> __ToolQueryFlattenFallback for T {}
+    };
+
+    // A struct-level `#[serde(default)]` makes every field optional
+    let struct_default = has_serde_default(&input.attrs);
+    // Options on the struct and each of its fields
+    let struct_attrs = match schema_attrs(&input.attrs, SchemaTarget::Struct) {
+        Ok(struct_attrs) => struct_attrs,
+        Err(err) => return err.to_compile_error().into(),
     };
+    let field_attrs = match fields
+        .iter()
+        .map(|field| schema_attrs(&field.attrs, SchemaTarget::Field))
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(field_attrs) => field_attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // A deprecated struct describes a deprecated tool
+    let struct_deprecated = struct_attrs.deprecated;
+    let deprecated_schema = struct_deprecated.then(|| {
+        quote! {
+            schema.insert("deprecated".to_string(), serde_json::Value::Bool(true));
+        }
+    });
+    let tool_description = if struct_deprecated {
+        quote! { format!("{description} (DEPRECATED)") }
+    } else {
+        quote! { description.to_string() }
+    };
+    let required_fields = fields
+        .iter()
+        .filter(|field| !has_serde_attr(&field.attrs, "flatten"))
+        .filter(|field| !struct_default && !is_option(&field.ty) && !has_serde_default(&field.attrs))
+        .map(|field| field.ident.as_ref().unwrap().to_string());
+
+    // Flattened fields contribute the required fields of their own type
+    let flattened_required = fields
+        .iter()
+        .filter(|field| has_serde_attr(&field.attrs, "flatten"))
+        .map(|field| {
+            let ty = &field.ty;
+            quote! {
+                {
+                    #flatten_fallback
+                    if let Some((_, flattened)) = <#ty>::__tool_query_flatten() {
+                        required.extend(flattened);
+                    }
+                }
+            }
+        });
+
+    // Flattened types without a schema may add any property
+    let open_flattened = fields
+        .iter()
+        .filter(|field| has_serde_attr(&field.attrs, "flatten"))
+        .map(|field| {
+            let ty = &field.ty;
+            quote! {
+                {
+                    #flatten_fallback
+                    <#ty>::__tool_query_flatten().is_none()
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let additional_properties = (!open_flattened.is_empty()).then(|| {
+        quote! {
+            if #(#open_flattened)||* {
+                schema.insert("additionalProperties".to_string(), serde_json::Value::Bool(true));
+            }
+        }
+    });
 
     // Generate field mappings
-    let field_mappings = fields.iter().map(|field| {
+    let field_mappings = fields.iter().zip(&field_attrs).map(|(field, schema_attrs)| {
         let field_name = field.ident.as_ref().unwrap().to_string();
-        let field_type = get_type_string(&field.ty);
+        if has_serde_attr(&field.attrs, "flatten") {
+            // Merge the properties of a flattened `ToolQuery` type. Other
+            // types, such as maps, add properties with names not known here,
+            // which `json_schema` allows for instead.
+            let ty = &field.ty;
+            return quote! {
+                {
+                    #flatten_fallback
+                    if let Some((properties, _)) = <#ty>::__tool_query_flatten() {
+                        map.extend(properties);
+                    }
+                }
+            };
+        }
+        let mut registered_type = None;
+        let field_type = match &schema_attrs.enum_values {
+            Some(_) => "string".to_string(),
+            None => match get_type_string(&field.ty) {
+                Some(field_type) => field_type,
+                None if cfg!(feature = "strict_schema") && schema_attrs.type_.is_none() => {
+                    let ty = &field.ty;
+                    return syn::Error::new_spanned(
+                        ty,
+                        format!(
+                            "field `{field_name}` has type `{}`, which has no known JSON Schema type; give one with #[schema(type = \"...\")]",
+                            type_name(ty)
+                        ),
+                    )
+                    .to_compile_error();
+                }
+                None => {
+                    registered_type = Some(option_inner(&field.ty).clone());
+                    schema_attrs
+                        .type_
+                        .as_ref()
+                        .map_or_else(|| "object".to_string(), syn::LitStr::value)
+                }
+            },
+        };
+        let const_mapping = schema_attrs.const_value.as_ref().map(|value| {
+            quote! {
+                field_map.insert("const".to_string(), serde_json::Value::from(#value));
+            }
+        });
+        let length_mapping = match (&schema_attrs.min_length, &schema_attrs.max_length) {
+            (None, None) => quote! {},
+            _ if field_type != "string" => {
+                return syn::Error::new_spanned(
+                    field,
+                    "min_length and max_length only apply to string fields",
+                )
+                .to_compile_error();
+            }
+            (min_length, max_length) => {
+                let min = min_length.as_ref().map(|min| {
+                    quote! {
+                        field_map.insert("minLength".to_string(), serde_json::Value::from(#min));
+                    }
+                });
+                let max = max_length.as_ref().map(|max| {
+                    quote! {
+                        field_map.insert("maxLength".to_string(), serde_json::Value::from(#max));
+                    }
+                });
+                let check = min_length.as_ref().zip(max_length.as_ref()).map(|(min, max)| {
+                    quote! {
+                        const _: () = assert!(#min <= #max, "min_length must not exceed max_length");
+                    }
+                });
+                quote! { #check #min #max }
+            }
+        };
+        let enum_mapping = schema_attrs.enum_values.as_ref().map(|enum_values| {
+            let values = match enum_values {
+                EnumValues::Type(enum_type) => quote! { #enum_type::enum_values() },
+                EnumValues::Literals(values) => quote! { vec![#(#values),*] },
+            };
+            quote! {
+                field_map.insert(
+                    "enum".to_string(),
+                    serde_json::Value::Array(
+                        #values.into_iter().map(serde_json::Value::from).collect(),
+                    ),
+                );
+            }
+        });
         
         // Extract doc comments, preserving all lines
         let docs = field.attrs.iter()
@@ -47,19 +253,69 @@ pub fn schema_gen(input: TokenStream) -> TokenStream {
             .join("\n")
             .trim()
             .to_string();
+        // Warn callers off deprecated fields in the only place they'll look
+        let deprecated = match &schema_attrs.deprecated_reason {
+            Some(reason) => Some(format!("(DEPRECATED: {reason})")),
+            None if field.attrs.iter().any(|attr| attr.path().is_ident("deprecated")) => {
+                Some("(DEPRECATED)".to_string())
+            }
+            None => None,
+        };
+        let docs = match deprecated {
+            Some(deprecated) if docs.is_empty() => deprecated,
+            Some(deprecated) => format!("{docs} {deprecated}"),
+            None => docs,
+        };
 
-        quote! {
+        // Types without a known schema may have one registered at runtime
+        let default_map = quote! {
             {
                 let mut field_map = serde_json::Map::new();
                 field_map.insert("type".to_string(), serde_json::Value::String(#field_type.to_string()));
+                field_map
+            }
+        };
+        let field_map = match registered_type {
+            Some(ty) => quote! {
+                ::mcp::tool_schema::ToolSchemaRegistry::get::<#ty>().unwrap_or_else(|| #default_map)
+            },
+            None => default_map,
+        };
+
+        quote! {
+            {
+                let mut field_map = #field_map;
                 field_map.insert("description".to_string(), serde_json::Value::String(#docs.to_string()));
+                #const_mapping
+                #enum_mapping
+                #length_mapping
                 map.insert(#field_name.to_string(), field_map);
             }
         }
     });
 
+    // Fields that can't be serialized can't be described as JSON either, so
+    // strict builds check for that up front
+    let serializable_check = cfg!(feature = "strict_schema").then(|| {
+        let checks = fields.iter().map(|field| {
+            let ty = &field.ty;
+            quote_spanned! {ty.span()=> __tool_query_assert_serialize::<#ty>(); }
+        });
+        quote! {
+            const _: () = {
+                fn __tool_query_assert_serialize<T: ?Sized + serde::Serialize>() {}
+                #[allow(dead_code)]
+                fn __tool_query_check_fields() {
+                    #(#checks)*
+                }
+            };
+        }
+    });
+
     // Generate the implementation
     let expanded = quote! {
+        #serializable_check
+
         impl #name {
             /// Generates a query tool schema representation of the struct's fields
             pub fn generate_schema() -> std::collections::HashMap<String, serde_json::Map<String, serde_json::Value>> {
@@ -67,15 +323,760 @@ pub fn schema_gen(input: TokenStream) -> TokenStream {
                 #(#field_mappings)*
                 map
             }
+
+            /// Names of the fields a caller is required to provide
+            pub fn required_fields() -> Vec<String> {
+                let mut required = vec![#(#required_fields.to_string()),*];
+                #(#flattened_required)*
+                required
+            }
+
+            /// Properties and required fields, for merging into structs that
+            /// flatten this one
+            #[doc(hidden)]
+            pub fn __tool_query_flatten() -> Option<(
+                std::collections::HashMap<String, serde_json::Map<String, serde_json::Value>>,
+                Vec<String>,
+            )> {
+                Some((Self::generate_schema(), Self::required_fields()))
+            }
+
+            /// Generates the full JSON Schema object describing the struct
+            pub fn json_schema() -> serde_json::Map<String, serde_json::Value> {
+                let properties = Self::generate_schema()
+                    .into_iter()
+                    .map(|(name, field)| (name, serde_json::Value::Object(field)))
+                    .collect();
+                let required = Self::required_fields()
+                    .into_iter()
+                    .map(serde_json::Value::String)
+                    .collect();
+                let mut schema = serde_json::Map::new();
+                schema.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                schema.insert("properties".to_string(), serde_json::Value::Object(properties));
+                schema.insert("required".to_string(), serde_json::Value::Array(required));
+                #additional_properties
+                #deprecated_schema
+                schema
+            }
+
+            /// Describes a tool taking this struct as its arguments, ready to
+            /// list in a `tools/list` response
+            pub fn to_tool(name: &str, description: &str) -> ::mcp::schema::original::Tool {
+                ::mcp::schema::original::Tool {
+                    description: Some(#tool_description),
+                    input_schema: ::mcp::schema::original::ToolInputSchema {
+                        properties: Self::generate_schema(),
+                        required: Self::required_fields(),
+                        type_: "object".to_string(),
+                    },
+                    name: name.to_string(),
+                }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Returns true if the attributes include `#[serde(default)]` or
+/// `#[serde(default = "...")]`
+fn has_serde_default(attrs: &[Attribute]) -> bool {
+    has_serde_attr(attrs, "default")
+}
+
+/// Returns true if the attributes include `#[serde(<name>)]` or
+/// `#[serde(<name> = "...")]`
+fn has_serde_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .any(|attr| {
+            if let Meta::List(ref list) = attr.meta {
+                let parser = Punctuated::<Meta, Token![,]>::parse_terminated;
+                if let Ok(metas) = parser.parse2(list.tokens.clone()) {
+                    return metas.iter().any(|meta| meta.path().is_ident(name));
+                }
+            }
+            false
+        })
+}
+
+/// A derive macro that converts a tool's return type into tool output. A
+/// struct with a single `String` field becomes text content, and one with a
+/// `Vec<u8>` field and a `mime_type: String` field becomes image content.
+/// Override the guess with `#[result(content_type = "text")]` or
+/// `#[result(content_type = "image")]`.
+#[proc_macro_derive(IntoServerResult, attributes(result))]
+pub fn into_server_result(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("IntoServerResult only supports structs with named fields"),
+        },
+        _ => panic!("IntoServerResult only supports structs"),
+    };
+
+    let string_fields = fields
+        .iter()
+        .filter(|field| is_type(&field.ty, "String"))
+        .collect::<Vec<_>>();
+    let bytes_field = fields.iter().find(|field| is_bytes(&field.ty));
+    let mime_type_field = string_fields
+        .iter()
+        .find(|field| field.ident.as_ref().map_or(false, |ident| ident == "mime_type"));
+
+    // Work out the content type, unless the user gave one
+    let mut content_type = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("result")) {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("content_type") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                content_type = Some(lit.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported result attribute"))
+            }
+        });
+        if let Err(err) = parsed {
+            return err.to_compile_error().into();
+        }
+    }
+    let content_type = content_type.unwrap_or_else(|| {
+        if bytes_field.is_some() && mime_type_field.is_some() {
+            "image".to_string()
+        } else {
+            "text".to_string()
+        }
+    });
+
+    let content = match content_type.as_str() {
+        "text" if fields.len() == 1 && string_fields.len() == 1 => {
+            let text = &string_fields[0].ident;
+            quote! { ::mcp::server::text_content(value.#text) }
+        }
+        "text" => {
+            return syn::Error::new_spanned(
+                name,
+                "text content requires a struct with a single `String` field",
+            )
+            .to_compile_error()
+            .into();
+        }
+        "image" => match (bytes_field, mime_type_field) {
+            (Some(bytes), Some(mime_type)) => {
+                let data = &bytes.ident;
+                let mime_type = &mime_type.ident;
+                quote! { ::mcp::server::image_content(&value.#data, value.#mime_type) }
+            }
+            _ => {
+                return syn::Error::new_spanned(
+                    name,
+                    "image content requires a `Vec<u8>` field and a `mime_type: String` field",
+                )
+                .to_compile_error()
+                .into();
+            }
+        },
+        other => {
+            return syn::Error::new_spanned(
+                name,
+                format!("unknown content type `{other}`, expected `text` or `image`"),
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    let expanded = quote! {
+        impl From<#name> for Vec<::mcp::server::ContentItem> {
+            fn from(value: #name) -> Self {
+                vec![#content]
+            }
+        }
+
+        impl From<#name> for ::mcp::schema::original::CallToolResult {
+            fn from(value: #name) -> Self {
+                let result: ::mcp::server::ToolCallResult = Ok(value.into());
+                result.into()
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// A derive macro that converts a prompt's output into prompt messages. Each
+/// of the `system`, `user` and `assistant` fields, which may be `String` or
+/// `Option<String>`, becomes a text message in field order. MCP has no system
+/// role, so `system` becomes a user message.
+#[proc_macro_derive(IntoPromptMessages)]
+pub fn into_prompt_messages(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("IntoPromptMessages only supports structs with named fields"),
+        },
+        _ => panic!("IntoPromptMessages only supports structs"),
+    };
+
+    let mut messages = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let role = match ident.to_string().as_str() {
+            "system" | "user" => quote! { ::mcp::schema::original::Role::User },
+            "assistant" => quote! { ::mcp::schema::original::Role::Assistant },
+            _ => {
+                return syn::Error::new_spanned(
+                    ident,
+                    "expected a `system`, `user` or `assistant` field",
+                )
+                .to_compile_error()
+                .into();
+            }
+        };
+        if !is_type(&field.ty, "String") && !is_option(&field.ty) {
+            return syn::Error::new_spanned(&field.ty, "expected `String` or `Option<String>`")
+                .to_compile_error()
+                .into();
+        }
+        messages.push(quote! {
+            if let Some(text) = Option::<String>::from(value.#ident) {
+                messages.push(::mcp::server::text_message(#role, text));
+            }
+        });
+    }
+
+    let expanded = quote! {
+        impl From<#name> for Vec<::mcp::schema::original::PromptMessage> {
+            fn from(value: #name) -> Self {
+                let mut messages = Vec::new();
+                #(#messages)*
+                messages
+            }
         }
     };
 
     TokenStream::from(expanded)
 }
 
-/// Helper function to convert Rust types to JSON Schema types
-fn get_type_string(ty: &syn::Type) -> String {
+/// A derive macro that makes a complete tool out of a struct of arguments.
+/// It generates everything `ToolQuery` does, plus a `<Name>Tool` struct
+/// implementing `Tool`, described by the struct's doc comments. Calls
+/// deserialize the arguments and pass them to the struct's
+/// `async fn call(args: Self) -> ToolCallResult`, which should be marked
+/// `#[tool_handler]`. A struct marked `#[schema(deprecated)]` has
+/// `(DEPRECATED)` appended to the tool's description.
+#[proc_macro_derive(McpTool, attributes(schema))]
+pub fn mcp_tool(input: TokenStream) -> TokenStream {
+    let query = schema_gen(input.clone());
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let vis = &input.vis;
+    let tool_name = format_ident!("{}Tool", name);
+    let docs = doc_string(&input.attrs);
+    // Invalid options were already reported by `schema_gen`
+    let deprecated = schema_attrs(&input.attrs, SchemaTarget::Struct)
+        .is_ok_and(|struct_attrs| struct_attrs.deprecated);
+    let docs = match deprecated {
+        true if docs.is_empty() => "(DEPRECATED)".to_string(),
+        true => format!("{docs} (DEPRECATED)"),
+        false => docs,
+    };
+    let description = if docs.is_empty() {
+        quote! { None }
+    } else {
+        quote! { Some(#docs) }
+    };
+
+    let expanded = quote! {
+        #[doc = concat!("Tool generated from [`", stringify!(#name), "`]")]
+        #vis struct #tool_name;
+
+        #[::mcp::async_trait::async_trait]
+        impl ::mcp::server::Tool for #tool_name {
+            fn description(&self) -> Option<&str> {
+                #description
+            }
+
+            fn input_schema(&self) -> ::mcp::schema::original::ToolInputSchema {
+                ::mcp::schema::original::ToolInputSchema {
+                    properties: #name::generate_schema(),
+                    required: #name::required_fields(),
+                    type_: "object".to_string(),
+                }
+            }
+
+            async fn call(
+                &self,
+                arguments: serde_json::Map<String, serde_json::Value>,
+            ) -> ::mcp::server::ToolCallResult {
+                let args: #name = serde_json::from_value(serde_json::Value::Object(arguments))
+                    .map_err(|err| ::mcp::server::ToolCallError::new(format!("Invalid arguments: {err}")))?;
+                #name::call(args).await
+            }
+        }
+    };
+
+    let mut output = query;
+    output.extend(TokenStream::from(expanded));
+    output
+}
+
+/// Marks the `call` method a `#[derive(McpTool)]` tool runs, checking that
+/// it is an `async fn` taking the arguments by value
+#[proc_macro_attribute]
+pub fn tool_handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let method = parse_macro_input!(item as syn::ImplItemFn);
+    let sig = &method.sig;
+    if sig.ident != "call" {
+        return syn::Error::new_spanned(&sig.ident, "a tool handler must be named `call`")
+            .to_compile_error()
+            .into();
+    }
+    if sig.asyncness.is_none() {
+        return syn::Error::new_spanned(sig.fn_token, "a tool handler must be async")
+            .to_compile_error()
+            .into();
+    }
+    if sig.receiver().is_some() || sig.inputs.len() != 1 {
+        return syn::Error::new_spanned(&sig.inputs, "a tool handler takes only `args: Self`")
+            .to_compile_error()
+            .into();
+    }
+    quote! { #method }.into()
+}
+
+/// An attribute macro that turns a function into an MCP prompt. For
+/// `#[prompt(name = "summarize", description = "Summarize text")]` on
+/// `fn summarize`, it generates a `SummarizePrompt` struct implementing
+/// `Prompt`. Each parameter becomes an argument, described by its doc
+/// comments and required unless it is an `Option`. Arguments are parsed from
+/// strings with `FromStr`.
+#[proc_macro_attribute]
+pub fn prompt(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut function = parse_macro_input!(item as syn::ItemFn);
+    let mut prompt_name = None;
+    let mut description = None;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            prompt_name = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            Ok(())
+        } else if meta.path.is_ident("description") {
+            description = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            Ok(())
+        } else {
+            Err(meta.error("unsupported prompt attribute"))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+
+    let fn_name = function.sig.ident.clone();
+    let prompt_name = prompt_name.unwrap_or_else(|| fn_name.to_string());
+    let description = match description {
+        Some(description) => quote! { Some(#description) },
+        None => quote! { None },
+    };
+    let struct_name = format_ident!("{}Prompt", to_pascal_case(&fn_name.to_string()));
+
+    let mut arguments = Vec::new();
+    let mut extractions = Vec::new();
+    let mut call_args = Vec::new();
+    for input in function.sig.inputs.iter_mut() {
+        let syn::FnArg::Typed(pat_type) = input else {
+            return syn::Error::new_spanned(input, "prompts can't take self")
+                .to_compile_error()
+                .into();
+        };
+        let syn::Pat::Ident(ref pat_ident) = *pat_type.pat else {
+            return syn::Error::new_spanned(&pat_type.pat, "prompt arguments must be named")
+                .to_compile_error()
+                .into();
+        };
+        let ident = pat_ident.ident.clone();
+        let arg_name = ident.to_string();
+        // Doc comments aren't allowed on parameters, so take them off here
+        let docs = doc_string(&pat_type.attrs);
+        pat_type.attrs.retain(|attr| !attr.path().is_ident("doc"));
+        let arg_description = if docs.is_empty() {
+            quote! { None }
+        } else {
+            quote! { Some(#docs.to_string()) }
+        };
+        let required = !is_option(&pat_type.ty);
+        arguments.push(quote! {
+            ::mcp::server::PromptArgument {
+                name: #arg_name.to_string(),
+                description: #arg_description,
+                required: #required,
+                default: None,
+            }
+        });
+        let invalid = quote! {
+            |_| ::mcp::error::McpError::Rpc {
+                code: ::mcp::error::INVALID_PARAMS,
+                message: format!("Invalid argument: {}", #arg_name),
+            }
+        };
+        extractions.push(if required {
+            quote! {
+                let #ident = arguments
+                    .get(#arg_name)
+                    .ok_or_else(|| ::mcp::error::McpError::Rpc {
+                        code: ::mcp::error::INVALID_PARAMS,
+                        message: format!("Missing argument: {}", #arg_name),
+                    })?
+                    .parse()
+                    .map_err(#invalid)?;
+            }
+        } else {
+            quote! {
+                let #ident = arguments
+                    .get(#arg_name)
+                    .map(|value| value.parse())
+                    .transpose()
+                    .map_err(#invalid)?;
+            }
+        });
+        call_args.push(ident);
+    }
+    let call = match function.sig.asyncness {
+        Some(_) => quote! { #fn_name(#(#call_args),*).await },
+        None => quote! { #fn_name(#(#call_args),*) },
+    };
+    let vis = &function.vis;
+
+    let expanded = quote! {
+        #function
+
+        #[doc = concat!("Prompt generated from [`", stringify!(#fn_name), "`]")]
+        #vis struct #struct_name;
+
+        #[::mcp::async_trait::async_trait]
+        impl ::mcp::server::Prompt for #struct_name {
+            fn name(&self) -> &str {
+                #prompt_name
+            }
+            fn description(&self) -> Option<&str> {
+                #description
+            }
+            fn arguments(&self) -> Vec<::mcp::server::PromptArgument> {
+                vec![#(#arguments),*]
+            }
+            async fn get(
+                &self,
+                arguments: std::collections::HashMap<String, String>,
+            ) -> Result<Vec<::mcp::schema::original::PromptMessage>, ::mcp::error::McpError> {
+                #(#extractions)*
+                Ok(#call)
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Converts a snake_case identifier to PascalCase
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Joins the doc comments in the attributes into a single string
+fn doc_string(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit),
+                    ..
+                }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Formats a type for messages, without the spacing tokens are printed with
+fn type_name(ty: &syn::Type) -> String {
+    quote!(#ty)
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Returns true if the type's last path segment is `name`
+fn is_type(ty: &syn::Type, name: &str) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Returns true if the type is a `Vec<u8>`
+fn is_bytes(ty: &syn::Type) -> bool {
+    let syn::Type::Path(type_path) = ty else {
+        return false;
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return false;
+    };
+    if segment.ident != "Vec" {
+        return false;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => matches!(
+            args.args.first(),
+            Some(syn::GenericArgument::Type(inner)) if is_type(inner, "u8")
+        ),
+        _ => false,
+    }
+}
+
+/// Options given in `#[schema(...)]` attributes
+#[derive(Default)]
+struct SchemaAttrs {
+    /// `const = ...`: the only value the field can hold
+    const_value: Option<syn::Expr>,
+    /// `enum_values = ...`: the values the field can hold
+    enum_values: Option<EnumValues>,
+    /// `deprecated_reason = "..."`: why the field should no longer be used
+    deprecated_reason: Option<String>,
+    /// `min_length = N`: the shortest string the field can hold
+    min_length: Option<syn::LitInt>,
+    /// `max_length = N`: the longest string the field can hold
+    max_length: Option<syn::LitInt>,
+    /// `deprecated`: the struct should no longer be used
+    deprecated: bool,
+    /// `type = "..."`: the JSON Schema type of a field the macro can't
+    /// work out on its own
+    type_: Option<syn::LitStr>,
+}
+
+/// Where the values of an `enum_values` field come from
+enum EnumValues {
+    /// `enum_values = "MyEnum"`: the values of a `ToolQuery` enum
+    Type(syn::Path),
+    /// `enum_values = ["a", "b"]`: the listed strings
+    Literals(Vec<syn::LitStr>),
+}
+
+/// Options `#[schema(...)]` accepts on a struct
+const STRUCT_SCHEMA_OPTIONS: &[&str] = &["deprecated"];
+/// Options `#[schema(...)]` accepts on a field
+const FIELD_SCHEMA_OPTIONS: &[&str] = &[
+    "const",
+    "enum_values",
+    "deprecated_reason",
+    "min_length",
+    "max_length",
+    "type",
+];
+/// JSON Schema types `#[schema(type = "...")]` accepts
+const JSON_SCHEMA_TYPES: &[&str] = &[
+    "string", "number", "integer", "boolean", "array", "object", "null",
+];
+
+/// What a `#[schema(...)]` attribute is on
+#[derive(Clone, Copy, PartialEq)]
+enum SchemaTarget {
+    Struct,
+    Field,
+}
+
+/// Parses the `#[schema(...)]` attributes of a struct or field, rejecting
+/// options that don't apply to it
+fn schema_attrs(attrs: &[Attribute], target: SchemaTarget) -> syn::Result<SchemaAttrs> {
+    let mut schema_attrs = SchemaAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("schema")) {
+        // `const` is a keyword, so this can't be parsed as a plain `Meta`
+        attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(ToString::to_string).unwrap_or_default();
+            let applies_to = if STRUCT_SCHEMA_OPTIONS.contains(&key.as_str()) {
+                SchemaTarget::Struct
+            } else if FIELD_SCHEMA_OPTIONS.contains(&key.as_str()) {
+                SchemaTarget::Field
+            } else {
+                return Err(meta.error(format!("unknown schema option `{key}`")));
+            };
+            if applies_to != target {
+                return Err(meta.error(match applies_to {
+                    SchemaTarget::Struct => format!("`{key}` only applies to structs"),
+                    SchemaTarget::Field => format!("`{key}` only applies to fields"),
+                }));
+            }
+            if meta.path.is_ident("const") {
+                schema_attrs.const_value = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("enum_values") {
+                let value = meta.value()?;
+                schema_attrs.enum_values = Some(if value.peek(syn::token::Bracket) {
+                    let values;
+                    syn::bracketed!(values in value);
+                    let values = Punctuated::<syn::LitStr, Token![,]>::parse_terminated(&values)?;
+                    EnumValues::Literals(values.into_iter().collect())
+                } else {
+                    let lit: syn::LitStr = value.parse()?;
+                    EnumValues::Type(lit.parse()?)
+                });
+            } else if meta.path.is_ident("deprecated_reason") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                schema_attrs.deprecated_reason = Some(lit.value());
+            } else if meta.path.is_ident("deprecated") {
+                schema_attrs.deprecated = true;
+            } else if meta.path.is_ident("min_length") {
+                schema_attrs.min_length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max_length") {
+                schema_attrs.max_length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("type") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                if !JSON_SCHEMA_TYPES.contains(&lit.value().as_str()) {
+                    return Err(syn::Error::new_spanned(
+                        &lit,
+                        format!("expected one of {}", JSON_SCHEMA_TYPES.join(", ")),
+                    ));
+                }
+                schema_attrs.type_ = Some(lit);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(schema_attrs)
+}
+
+/// Generates the value list and string parsing for an enum of unit variants
+fn enum_schema_gen(name: &syn::Ident, data: &syn::DataEnum) -> TokenStream {
+    if let Some(variant) = data
+        .variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return syn::Error::new_spanned(variant, "ToolQuery only supports enums of unit variants")
+            .to_compile_error()
+            .into();
+    }
+    let idents = data.variants.iter().map(|variant| &variant.ident).collect::<Vec<_>>();
+    let values = data
+        .variants
+        .iter()
+        .map(|variant| serde_rename(&variant.attrs).unwrap_or_else(|| variant.ident.to_string()))
+        .collect::<Vec<_>>();
+
+    let expanded = quote! {
+        impl #name {
+            /// Values the enum can take, as they appear in JSON
+            pub fn enum_values() -> Vec<&'static str> {
+                vec![#(#values),*]
+            }
+
+            /// Deserializes the enum from a string via `FromStr`
+            pub fn deserialize_from_str<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let value = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl std::str::FromStr for #name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    #(#values => Ok(Self::#idents),)*
+                    _ => Err(format!("unknown variant `{}`, expected one of {:?}", s, Self::enum_values())),
+                }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Returns the value of `#[serde(rename = "...")]`, if present
+fn serde_rename(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .find_map(|attr| {
+            if let Meta::List(ref list) = attr.meta {
+                let parser = Punctuated::<Meta, Token![,]>::parse_terminated;
+                if let Ok(metas) = parser.parse2(list.tokens.clone()) {
+                    return metas.into_iter().find_map(|meta| match meta {
+                        Meta::NameValue(meta) if meta.path.is_ident("rename") => match meta.value {
+                            syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            }) => Some(lit.value()),
+                            _ => None,
+                        },
+                        _ => None,
+                    });
+                }
+            }
+            None
+        })
+}
+
+/// Returns true if the type is an `Option<T>`
+fn is_option(ty: &syn::Type) -> bool {
     match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Returns the type inside an `Option`, or the type itself otherwise
+fn option_inner(ty: &syn::Type) -> &syn::Type {
+    if let syn::Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" {
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                        return option_inner(inner);
+                    }
+                }
+            }
+        }
+    }
+    ty
+}
+
+/// Helper function to convert Rust types to JSON Schema types. Returns `None`
+/// for types it doesn't recognize.
+fn get_type_string(ty: &syn::Type) -> Option<String> {
+    let type_string = match ty {
         syn::Type::Path(type_path) => {
             let segment = type_path.path.segments.last().unwrap();
             match segment.ident.to_string().as_str() {
@@ -83,8 +1084,27 @@ fn get_type_string(ty: &syn::Type) -> String {
                 "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "f32" | "f64" => {
                     "number".to_string()
                 }
+                // `serde_json::Number`, either an integer or a float
+                "Number" => "number".to_string(),
                 "bool" => "boolean".to_string(),
                 "Vec" => "array".to_string(),
+                // Maps with string keys serialize as JSON objects
+                "HashMap" | "BTreeMap" | "Map" => "object".to_string(),
+                "IndexMap" if cfg!(feature = "indexmap") => "object".to_string(),
+                // Smart pointers to strings serialize as strings
+                "Arc" | "Rc" | "Box" => {
+                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                        if let Some(syn::GenericArgument::Type(syn::Type::Path(inner))) =
+                            args.args.first()
+                        {
+                            let inner = &inner.path.segments.last().unwrap().ident;
+                            if inner == "str" || inner == "String" {
+                                return Some("string".to_string());
+                            }
+                        }
+                    }
+                    return None;
+                }
                 "Option" => {
                     if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                         if let Some(arg) = args.args.first() {
@@ -95,9 +1115,10 @@ fn get_type_string(ty: &syn::Type) -> String {
                     }
                     "unknown".to_string()
                 }
-                _ => "object".to_string(),
+                _ => return None,
             }
         }
         _ => "unknown".to_string(),
-    }
+    };
+    Some(type_string)
 }